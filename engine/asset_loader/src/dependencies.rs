@@ -0,0 +1,124 @@
+use std::{collections::HashMap, hash::Hash};
+
+use bevy::{asset::LoadContext, prelude::*};
+
+use crate::error::AssetLoadError;
+
+/// Starts loading the assets an asset refers to, implemented with `#[derive(PhosAsset)]`
+pub trait LoadDependencies
+{
+	fn load_dependencies(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), AssetLoadError>;
+}
+
+impl<T: LoadDependencies> LoadDependencies for Option<T>
+{
+	fn load_dependencies(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), AssetLoadError>
+	{
+		if let Some(value) = self {
+			return value.load_dependencies(load_context);
+		}
+		return Ok(());
+	}
+}
+
+impl<T: LoadDependencies> LoadDependencies for Vec<T>
+{
+	fn load_dependencies(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), AssetLoadError>
+	{
+		for value in self {
+			value.load_dependencies(load_context)?;
+		}
+		return Ok(());
+	}
+}
+
+impl<K, T: LoadDependencies> LoadDependencies for HashMap<K, T>
+{
+	fn load_dependencies(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), AssetLoadError>
+	{
+		for value in self.values_mut() {
+			value.load_dependencies(load_context)?;
+		}
+		return Ok(());
+	}
+}
+
+/// Turns the asset paths of a field into handles, `H` is the type of the field holding the handles
+pub trait LoadPaths<H>
+{
+	/// `field` is the name of the path field, used for errors
+	fn load_paths(&self, load_context: &mut LoadContext<'_>, field: &'static str) -> Result<H, AssetLoadError>;
+}
+
+/// A single path is optional, an empty path gives a default handle
+impl<A: Asset> LoadPaths<Handle<A>> for String
+{
+	fn load_paths(&self, load_context: &mut LoadContext<'_>, _field: &'static str)
+	-> Result<Handle<A>, AssetLoadError>
+	{
+		if self.is_empty() {
+			return Ok(Handle::default());
+		}
+		return Ok(load_context.load(self));
+	}
+}
+
+impl<A: Asset> LoadPaths<Option<Handle<A>>> for Option<String>
+{
+	fn load_paths(
+		&self,
+		load_context: &mut LoadContext<'_>,
+		field: &'static str,
+	) -> Result<Option<Handle<A>>, AssetLoadError>
+	{
+		return self
+			.as_ref()
+			.map(|path| load_required(load_context, path, field))
+			.transpose();
+	}
+}
+
+impl<A: Asset> LoadPaths<Vec<Handle<A>>> for Vec<String>
+{
+	fn load_paths(
+		&self,
+		load_context: &mut LoadContext<'_>,
+		field: &'static str,
+	) -> Result<Vec<Handle<A>>, AssetLoadError>
+	{
+		return self
+			.iter()
+			.map(|path| load_required(load_context, path, field))
+			.collect();
+	}
+}
+
+impl<K: Clone + Eq + Hash, A: Asset> LoadPaths<HashMap<K, Handle<A>>> for HashMap<K, String>
+{
+	fn load_paths(
+		&self,
+		load_context: &mut LoadContext<'_>,
+		field: &'static str,
+	) -> Result<HashMap<K, Handle<A>>, AssetLoadError>
+	{
+		return self
+			.iter()
+			.map(|(key, path)| Ok((key.clone(), load_required(load_context, path, field)?)))
+			.collect();
+	}
+}
+
+fn load_required<A: Asset>(
+	load_context: &mut LoadContext<'_>,
+	path: &String,
+	field: &'static str,
+) -> Result<Handle<A>, AssetLoadError>
+{
+	if path.is_empty() {
+		return Err(AssetLoadError::MissingDependency {
+			path: load_context.asset_path().to_string(),
+			field,
+		});
+	}
+	return Ok(load_context.load(path));
+}
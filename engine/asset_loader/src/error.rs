@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+/// Errors of the loaders created by [`PhosAsset`](crate::PhosAsset), all of them name the asset file
+#[derive(Debug)]
+pub enum AssetLoadError
+{
+	Io
+	{
+		path: String, source: std::io::Error
+	},
+	/// The file cannot be parsed or does not match the asset, `line` and `column` start at 1
+	Parse
+	{
+		path: String,
+		line: usize,
+		column: usize,
+		message: String,
+		snippet: String,
+	},
+	/// A path to another asset is empty
+	MissingDependency
+	{
+		path: String, field: &'static str
+	},
+	/// The asset was read but [`ValidateAsset::validate`] rejected it
+	Validation
+	{
+		path: String, message: String
+	},
+}
+
+impl AssetLoadError
+{
+	/// Creates a [`AssetLoadError::Parse`] pointing at `line` and `column` of `source`
+	pub fn parse(path: &str, source: &[u8], line: usize, column: usize, message: String) -> Self
+	{
+		return AssetLoadError::Parse {
+			path: path.to_string(),
+			line,
+			column,
+			message,
+			snippet: get_snippet(&String::from_utf8_lossy(source), line, column),
+		};
+	}
+
+	pub fn get_path(&self) -> &str
+	{
+		return match self {
+			AssetLoadError::Io { path, .. } => path,
+			AssetLoadError::Parse { path, .. } => path,
+			AssetLoadError::MissingDependency { path, .. } => path,
+			AssetLoadError::Validation { path, .. } => path,
+		};
+	}
+}
+
+impl Display for AssetLoadError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			AssetLoadError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+			AssetLoadError::Parse {
+				path,
+				line,
+				column,
+				message,
+				snippet,
+			} => write!(
+				f,
+				"Failed to parse {}:{}:{}: {}\n{}",
+				path, line, column, message, snippet
+			),
+			AssetLoadError::MissingDependency { path, field } => {
+				write!(f, "{} has an empty path in '{}'", path, field)
+			}
+			AssetLoadError::Validation { path, message } => write!(f, "{} is invalid: {}", path, message),
+		};
+	}
+}
+
+impl std::error::Error for AssetLoadError
+{
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+	{
+		return match self {
+			AssetLoadError::Io { source, .. } => Some(source),
+			_ => None,
+		};
+	}
+}
+
+/// Checks run on an asset after it was deserialized, every asset with a [`PhosAsset`](crate::PhosAsset) loader implements it
+pub trait ValidateAsset
+{
+	/// Returns a description of the problem when the asset cannot be used
+	fn validate(&self) -> Result<(), String>
+	{
+		return Ok(());
+	}
+}
+
+/// Gets the line of `source` with a marker under `column`, both starting at 1
+pub fn get_snippet(source: &str, line: usize, column: usize) -> String
+{
+	let Some(text) = source.lines().nth(line.saturating_sub(1)) else {
+		return String::new();
+	};
+	//Keep tabs so the marker lines up with the text
+	let indent: String = text
+		.chars()
+		.take(column.saturating_sub(1))
+		.map(|c| if c == '\t' { '\t' } else { ' ' })
+		.collect();
+	let number = line.to_string();
+	let gutter = " ".repeat(number.len());
+	return format!("{} |\n{} | {}\n{} | {}^", gutter, number, text, gutter, indent);
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn snippet()
+	{
+		let source = "(\n\tname: \"Tile\",\n\tmove_cost: one,\n)";
+		assert_eq!(
+			get_snippet(source, 3, 13),
+			"  |\n3 | \tmove_cost: one,\n  | \t           ^"
+		);
+		assert_eq!(get_snippet(source, 9, 1), "");
+
+		let err = AssetLoadError::parse("tiles/grass.tile", source.as_bytes(), 3, 13, "Expected float".into());
+		assert_eq!(err.get_path(), "tiles/grass.tile");
+		assert!(
+			err.to_string()
+				.starts_with("Failed to parse tiles/grass.tile:3:13: Expected float\n")
+		);
+	}
+}
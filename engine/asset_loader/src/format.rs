@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::AssetLoadError;
+
+/// File formats assets can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetFormat
+{
+	Ron,
+	Json,
+	Toml,
+}
+
+impl AssetFormat
+{
+	/// Picks the format from the last extension, `.json` and `.toml` files use those formats and everything else is RON
+	pub fn from_path(path: &Path) -> Self
+	{
+		return match path.extension().and_then(|e| e.to_str()) {
+			Some("json") => AssetFormat::Json,
+			Some("toml") => AssetFormat::Toml,
+			_ => AssetFormat::Ron,
+		};
+	}
+
+	/// Deserializes `bytes`, errors point at the position in the file
+	pub fn deserialize<T: DeserializeOwned>(&self, path: &str, bytes: &[u8]) -> Result<T, AssetLoadError>
+	{
+		return match self {
+			AssetFormat::Ron => ron::de::from_bytes(bytes).map_err(|err| {
+				AssetLoadError::parse(
+					path,
+					bytes,
+					err.span.start.line,
+					err.span.start.col,
+					err.code.to_string(),
+				)
+			}),
+			AssetFormat::Json => serde_json::from_slice(bytes)
+				.map_err(|err| AssetLoadError::parse(path, bytes, err.line(), err.column(), err.to_string())),
+			AssetFormat::Toml => {
+				let text = std::str::from_utf8(bytes).map_err(|err| {
+					let (line, column) = get_line_column(bytes, err.valid_up_to());
+					return AssetLoadError::parse(path, bytes, line, column, err.to_string());
+				})?;
+				toml::from_str(text).map_err(|err| {
+					let (line, column) = get_line_column(bytes, err.span().map_or(0, |s| s.start));
+					return AssetLoadError::parse(path, bytes, line, column, err.message().to_string());
+				})
+			}
+		};
+	}
+}
+
+/// Converts a byte offset into a line and column, both starting at 1
+pub fn get_line_column(source: &[u8], offset: usize) -> (usize, usize)
+{
+	let before = &source[..offset.min(source.len())];
+	let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+	let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+	let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+	return (line, column);
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn format_from_path()
+	{
+		assert_eq!(AssetFormat::from_path(Path::new("a/grass.tile")), AssetFormat::Ron);
+		assert_eq!(AssetFormat::from_path(Path::new("a/grass.tile.ron")), AssetFormat::Ron);
+		assert_eq!(
+			AssetFormat::from_path(Path::new("a/mine.building.json")),
+			AssetFormat::Json
+		);
+		assert_eq!(
+			AssetFormat::from_path(Path::new("a/mine.building.toml")),
+			AssetFormat::Toml
+		);
+	}
+
+	#[test]
+	fn line_column()
+	{
+		let source = "name = 1\n\tcost = x\n".as_bytes();
+		assert_eq!(get_line_column(source, 0), (1, 1));
+		assert_eq!(get_line_column(source, 7), (1, 8));
+		assert_eq!(get_line_column(source, 9), (2, 1));
+		assert_eq!(get_line_column(source, 17), (2, 9));
+		assert_eq!(get_line_column(source, 100), (3, 1));
+	}
+
+	#[test]
+	fn json_error_position()
+	{
+		let source = "{\n\t\"name\": \"Mine\",\n\t\"health\": ten\n}";
+		let err = AssetFormat::Json
+			.deserialize::<serde_json::Value>("mine.building.json", source.as_bytes())
+			.unwrap_err();
+		let AssetLoadError::Parse { line, snippet, .. } = err else {
+			panic!("Expected a parse error");
+		};
+		assert_eq!(line, 3);
+		assert!(snippet.contains("\"health\": ten"));
+	}
+}
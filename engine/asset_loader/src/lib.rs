@@ -1 +1,6 @@
-pub mod macros;
+pub mod dependencies;
+pub mod error;
+pub mod format;
+pub mod loader;
+
+pub use asset_loader_derive::PhosAsset;
@@ -0,0 +1,27 @@
+use bevy::asset::{LoadContext, io::Reader};
+use serde::de::DeserializeOwned;
+
+use crate::{
+	dependencies::LoadDependencies,
+	error::{AssetLoadError, ValidateAsset},
+	format::AssetFormat,
+};
+
+/// Reads, validates and loads the dependencies of an asset, used by the loaders of `#[derive(PhosAsset)]`
+pub async fn load_asset<A>(reader: &mut dyn Reader, load_context: &mut LoadContext<'_>) -> Result<A, AssetLoadError>
+where
+	A: DeserializeOwned + ValidateAsset + LoadDependencies,
+{
+	let path = load_context.asset_path().to_string();
+	let mut bytes = Vec::new();
+	if let Err(source) = reader.read_to_end(&mut bytes).await {
+		return Err(AssetLoadError::Io { path, source });
+	}
+	let format = AssetFormat::from_path(load_context.asset_path().path());
+	let mut asset: A = format.deserialize(&path, &bytes)?;
+	if let Err(message) = asset.validate() {
+		return Err(AssetLoadError::Validation { path, message });
+	}
+	asset.load_dependencies(load_context)?;
+	return Ok(asset);
+}
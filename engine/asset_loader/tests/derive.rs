@@ -0,0 +1,160 @@
+use std::{collections::HashMap, path::Path};
+
+use asset_loader::{PhosAsset, error::ValidateAsset};
+use bevy::{
+	asset::{
+		AssetLoader,
+		io::{
+			AssetSourceBuilder, AssetSourceId,
+			memory::{Dir, MemoryAssetReader},
+		},
+	},
+	prelude::*,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, PhosAsset)]
+#[asset(extensions = ["part", "part.ron"])]
+struct PartAsset
+{
+	name: String,
+}
+
+impl ValidateAsset for PartAsset {}
+
+/// Not an asset itself, loaded through the `nested` field of [`MachineAsset`]
+#[derive(Deserialize, PhosAsset)]
+struct Slot
+{
+	part: String,
+	#[serde(skip)]
+	#[asset(path = "part")]
+	part_handle: Handle<PartAsset>,
+}
+
+#[derive(Deserialize, Asset, TypePath, PhosAsset)]
+#[asset(extensions = ["machine"], loader = MachineReader, plugin = MachinePlugin)]
+struct MachineAsset
+{
+	base: String,
+	#[serde(skip)]
+	#[asset(path = "base")]
+	base_handle: Handle<PartAsset>,
+	#[serde(default)]
+	extra: Option<String>,
+	#[serde(skip)]
+	#[asset(path = "extra")]
+	extra_handle: Option<Handle<PartAsset>>,
+	parts: Vec<String>,
+	#[serde(skip)]
+	#[asset(path = "parts")]
+	part_handles: Vec<Handle<PartAsset>>,
+	named_parts: HashMap<String, String>,
+	#[serde(skip)]
+	#[asset(path = "named_parts")]
+	named_part_handles: HashMap<String, Handle<PartAsset>>,
+	#[asset(nested)]
+	slots: Vec<Slot>,
+}
+
+impl ValidateAsset for MachineAsset {}
+
+fn create_app(dir: Dir) -> App
+{
+	let mut app = App::new();
+	app.register_asset_source(
+		AssetSourceId::Default,
+		AssetSourceBuilder::new(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+	);
+	app.add_plugins((
+		TaskPoolPlugin::default(),
+		AssetPlugin {
+			watch_for_changes_override: Some(false),
+			use_asset_processor_override: Some(false),
+			..default()
+		},
+		PartAssetPlugin,
+		MachinePlugin,
+	));
+	return app;
+}
+
+fn load_machine(app: &mut App, path: &'static str) -> Handle<MachineAsset>
+{
+	let handle = app.world().resource::<AssetServer>().load(path);
+	for _ in 0..10000 {
+		app.update();
+		let server = app.world().resource::<AssetServer>();
+		if server.is_loaded_with_dependencies(&handle) || server.load_state(&handle).is_failed() {
+			break;
+		}
+	}
+	return handle;
+}
+
+#[test]
+fn loader_names()
+{
+	assert_eq!(PartAssetLoader.extensions(), ["part", "part.ron"]);
+	assert_eq!(MachineReader.extensions(), ["machine"]);
+}
+
+#[test]
+fn load_dependencies()
+{
+	let dir = Dir::default();
+	for part in ["frame", "arm", "leg", "wheel", "bolt"] {
+		dir.insert_asset_text(Path::new(&format!("{}.part", part)), &format!("(name: \"{}\")", part));
+	}
+	dir.insert_asset_text(
+		Path::new("robot.machine"),
+		r#"(
+			base: "frame.part",
+			extra: Some("arm.part"),
+			parts: ["leg.part", "leg.part"],
+			named_parts: {"drive": "wheel.part"},
+			slots: [(part: "bolt.part")],
+		)"#,
+	);
+	let mut app = create_app(dir);
+	let handle = load_machine(&mut app, "robot.machine");
+
+	let server = app.world().resource::<AssetServer>();
+	assert!(server.is_loaded_with_dependencies(&handle));
+	let machine = app.world().resource::<Assets<MachineAsset>>().get(&handle).unwrap();
+	let get_path = |handle: &Handle<PartAsset>| server.get_path(handle.id()).unwrap().to_string();
+	assert_eq!(get_path(&machine.base_handle), "frame.part");
+	assert_eq!(get_path(machine.extra_handle.as_ref().unwrap()), "arm.part");
+	assert_eq!(machine.part_handles.len(), 2);
+	assert_eq!(machine.part_handles[0], machine.part_handles[1]);
+	assert_eq!(get_path(&machine.part_handles[0]), "leg.part");
+	assert_eq!(get_path(&machine.named_part_handles["drive"]), "wheel.part");
+	assert_eq!(get_path(&machine.slots[0].part_handle), "bolt.part");
+
+	let parts = app.world().resource::<Assets<PartAsset>>();
+	assert_eq!(parts.get(&machine.slots[0].part_handle).unwrap().name, "bolt");
+}
+
+#[test]
+fn optional_paths()
+{
+	let dir = Dir::default();
+	dir.insert_asset_text(Path::new("frame.part"), "(name: \"frame\")");
+	dir.insert_asset_text(
+		Path::new("minimal.machine"),
+		r#"(base: "frame.part", parts: [], named_parts: {}, slots: [])"#,
+	);
+	//Paths in a list are required, an empty one fails the load
+	dir.insert_asset_text(
+		Path::new("broken.machine"),
+		r#"(base: "frame.part", parts: [""], named_parts: {}, slots: [])"#,
+	);
+	let mut app = create_app(dir);
+	let minimal = load_machine(&mut app, "minimal.machine");
+	let broken = load_machine(&mut app, "broken.machine");
+
+	let machine = app.world().resource::<Assets<MachineAsset>>().get(&minimal).unwrap();
+	assert!(machine.extra_handle.is_none());
+	assert!(machine.part_handles.is_empty());
+	assert!(app.world().resource::<AssetServer>().load_state(&broken).is_failed());
+}
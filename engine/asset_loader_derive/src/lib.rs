@@ -0,0 +1,188 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Field, Fields, Ident, LitStr, Token, parse_macro_input, punctuated::Punctuated};
+
+/// Derives `LoadDependencies` for an asset, with `#[asset(extensions = [..])]` also its loader and plugin.
+///
+/// Container attributes:
+/// - `extensions = ["building", "building.ron"]` creates `{Name}Loader` and `{Name}Plugin` for those extensions
+/// - `loader = Ident` and `plugin = Ident` rename the generated types
+///
+/// Field attributes:
+/// - `path = "field"` fills the handle field from the paths in `field`,
+///   works for `String`, `Option<String>`, `Vec<String>` and `HashMap<K, String>`
+/// - `nested` loads the dependencies of a field that derives `PhosAsset` itself, also inside `Option`, `Vec` and `HashMap`
+#[proc_macro_derive(PhosAsset, attributes(asset))]
+pub fn derive_phos_asset(input: TokenStream) -> TokenStream
+{
+	let input = parse_macro_input!(input as DeriveInput);
+	return match expand(&input) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	};
+}
+
+#[derive(Default)]
+struct ContainerAttributes
+{
+	extensions: Vec<LitStr>,
+	loader: Option<Ident>,
+	plugin: Option<Ident>,
+}
+
+enum FieldAttribute
+{
+	Path(Ident),
+	Nested,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2>
+{
+	let name = &input.ident;
+	let Data::Struct(data) = &input.data else {
+		return Err(syn::Error::new_spanned(
+			name,
+			"PhosAsset can only be derived for structs",
+		));
+	};
+	let Fields::Named(fields) = &data.fields else {
+		return Err(syn::Error::new_spanned(
+			name,
+			"PhosAsset needs a struct with named fields",
+		));
+	};
+
+	let mut loads = Vec::new();
+	for field in &fields.named {
+		let ident = field.ident.as_ref().expect("Named fields have an ident");
+		match parse_field(field)? {
+			Some(FieldAttribute::Path(path)) => {
+				let path_name = path.to_string();
+				loads.push(quote! {
+					self.#ident = ::asset_loader::dependencies::LoadPaths::load_paths(&self.#path, load_context, #path_name)?;
+				});
+			}
+			Some(FieldAttribute::Nested) => {
+				loads.push(quote! {
+					::asset_loader::dependencies::LoadDependencies::load_dependencies(&mut self.#ident, load_context)?;
+				});
+			}
+			None => {}
+		}
+	}
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let mut tokens = quote! {
+		impl #impl_generics ::asset_loader::dependencies::LoadDependencies for #name #ty_generics #where_clause {
+			#[allow(unused_variables)]
+			fn load_dependencies(
+				&mut self,
+				load_context: &mut ::bevy::asset::LoadContext<'_>,
+			) -> ::std::result::Result<(), ::asset_loader::error::AssetLoadError> {
+				#(#loads)*
+				return ::std::result::Result::Ok(());
+			}
+		}
+	};
+
+	let container = parse_container(input)?;
+	if container.extensions.is_empty() {
+		if container.loader.is_some() || container.plugin.is_some() {
+			return Err(syn::Error::new_spanned(name, "`loader` and `plugin` need `extensions`"));
+		}
+		return Ok(tokens);
+	}
+	if !input.generics.params.is_empty() {
+		return Err(syn::Error::new_spanned(
+			&input.generics,
+			"Assets with a loader cannot be generic",
+		));
+	}
+	let loader = container.loader.unwrap_or_else(|| format_ident!("{}Loader", name));
+	let plugin = container.plugin.unwrap_or_else(|| format_ident!("{}Plugin", name));
+	let extensions = &container.extensions;
+	let vis = &input.vis;
+
+	tokens.extend(quote! {
+		#vis struct #plugin;
+
+		impl ::bevy::app::Plugin for #plugin {
+			fn build(&self, app: &mut ::bevy::app::App) {
+				use ::bevy::asset::AssetApp;
+				app.init_asset::<#name>().init_asset_loader::<#loader>();
+			}
+		}
+
+		#[derive(Default, ::bevy::reflect::TypePath)]
+		#vis struct #loader;
+
+		impl ::bevy::asset::AssetLoader for #loader {
+			type Asset = #name;
+			type Settings = ();
+			type Error = ::asset_loader::error::AssetLoadError;
+
+			async fn load(
+				&self,
+				reader: &mut dyn ::bevy::asset::io::Reader,
+				_settings: &Self::Settings,
+				load_context: &mut ::bevy::asset::LoadContext<'_>,
+			) -> ::std::result::Result<Self::Asset, Self::Error> {
+				return ::asset_loader::loader::load_asset::<#name>(reader, load_context).await;
+			}
+
+			fn extensions(&self) -> &[&str] {
+				return &[#(#extensions),*];
+			}
+		}
+	});
+	return Ok(tokens);
+}
+
+fn parse_container(input: &DeriveInput) -> syn::Result<ContainerAttributes>
+{
+	let mut container = ContainerAttributes::default();
+	for attr in input.attrs.iter().filter(|a| a.path().is_ident("asset")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("extensions") {
+				let value = meta.value()?;
+				let content;
+				syn::bracketed!(content in value);
+				container
+					.extensions
+					.extend(Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?);
+				return Ok(());
+			}
+			if meta.path.is_ident("loader") {
+				container.loader = Some(meta.value()?.parse()?);
+				return Ok(());
+			}
+			if meta.path.is_ident("plugin") {
+				container.plugin = Some(meta.value()?.parse()?);
+				return Ok(());
+			}
+			return Err(meta.error("Unknown asset attribute, expected `extensions`, `loader` or `plugin`"));
+		})?;
+	}
+	return Ok(container);
+}
+
+fn parse_field(field: &Field) -> syn::Result<Option<FieldAttribute>>
+{
+	let mut attribute = None;
+	for attr in field.attrs.iter().filter(|a| a.path().is_ident("asset")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("path") {
+				let path: LitStr = meta.value()?.parse()?;
+				attribute = Some(FieldAttribute::Path(path.parse()?));
+				return Ok(());
+			}
+			if meta.path.is_ident("nested") {
+				attribute = Some(FieldAttribute::Nested);
+				return Ok(());
+			}
+			return Err(meta.error("Unknown asset field attribute, expected `path` or `nested`"));
+		})?;
+	}
+	return Ok(attribute);
+}
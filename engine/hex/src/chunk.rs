@@ -6,6 +6,8 @@ pub struct Chunk
 {
 	pub heights: [f32; Chunk::AREA],
 	pub textures: [[u32; 2]; Chunk::AREA],
+	/// Id of the tile type painted on each tile
+	pub tile_types: [usize; Chunk::AREA],
 	// pub biome_data: [BiomeData; Chunk::AREA],
 	pub biome_id: [usize; Chunk::AREA],
 	pub chunk_offset: IVec2,
@@ -20,6 +22,7 @@ impl Default for Chunk
 		Self {
 			heights: [0.; Chunk::AREA],
 			textures: [[0; 2]; Chunk::AREA],
+			tile_types: [0; Chunk::AREA],
 			// biome_data: [BiomeData::default(); Chunk::AREA],
 			biome_id: [0; Chunk::AREA],
 			chunk_offset: Default::default(),
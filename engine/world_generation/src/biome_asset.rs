@@ -1,33 +1,59 @@
-use asset_loader::create_asset_loader;
+use asset_loader::{PhosAsset, error::ValidateAsset};
+use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{prelude::NoiseConfig, tile_mapper::TileMapperAsset};
 
-#[derive(Serialize, Deserialize, Asset, TypePath, Debug, Clone)]
+#[derive(Serialize, Deserialize, Asset, TypePath, Debug, Clone, PhosAsset)]
+#[asset(extensions = ["biome", "biome.ron", "biome.json", "biome.toml"])]
 pub struct BiomeAsset {
+	/// Key content packs override the biome by, the file name is used when it is empty
+	#[serde(default)]
+	pub identifier: String,
 	pub moisture: f32,
 	pub temperature: f32,
 	pub continentality: f32,
 	pub name: String,
 	#[serde(skip)]
+	#[asset(path = "tile_mapper_path")]
 	pub tile_mapper: Handle<TileMapperAsset>,
 	pub tile_mapper_path: String,
 	pub noise: NoiseConfig,
+	/// Names of the biomes this biome may border, all biomes are allowed when not set
+	#[serde(default)]
+	pub allowed_neighbors: Option<Vec<String>>,
+	/// Biomes to place along the border with specific neighbors, e.g. a beach next to the ocean
+	#[serde(default)]
+	pub transitions: Vec<BiomeTransition>,
+	/// Regions of this biome with fewer tiles than this are merged into their neighbors
+	#[serde(default)]
+	pub min_region_size: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BiomeTransition {
+	/// Name of the neighboring biome
+	pub neighbor: String,
+	/// Name of the biome placed on this biome's side of the border
+	pub biome: String,
+	/// Width of the transition in tiles
+	#[serde(default = "default_transition_width")]
+	pub width: usize,
+}
+
+fn default_transition_width() -> usize {
+	return 1;
 }
 
 impl BiomeAsset {
 	pub fn distance(&self, data: Vec3) -> f32 {
-		let a = Vec3::new(self.moisture, self.temperature, self.continentality);
-		return (a - data).length();
+		return (self.climate() - data).length();
+	}
+
+	pub fn climate(&self) -> Vec3 {
+		return Vec3::new(self.moisture, self.temperature, self.continentality);
 	}
 }
 
-create_asset_loader!(
-	BiomeAssetPlugin,
-	BiomeAssetLoader,
-	BiomeAsset,
-	&["biome", "biome.ron"],
-	tile_mapper_path -> tile_mapper
-	;
-	?
-);
+impl ValidateAsset for BiomeAsset {}
+
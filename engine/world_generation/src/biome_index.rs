@@ -0,0 +1,173 @@
+use bevy::math::Vec3;
+
+/// A k-d tree over biome climate points (moisture, temperature, continentality).
+///
+/// Used by [`crate::biome_painter::BiomePainter`] to find the closest biome for a tile without scanning every biome.
+#[derive(Clone, Default, Debug)]
+pub struct BiomeIndex
+{
+	nodes: Vec<BiomeIndexNode>,
+	root: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BiomeIndexNode
+{
+	point: Vec3,
+	biome: usize,
+	axis: usize,
+	left: Option<usize>,
+	right: Option<usize>,
+}
+
+impl BiomeIndex
+{
+	pub fn new(points: &[Vec3]) -> Self
+	{
+		let mut index = BiomeIndex {
+			nodes: Vec::with_capacity(points.len()),
+			root: None,
+		};
+		let mut items: Vec<(usize, Vec3)> = points.iter().copied().enumerate().collect();
+		index.root = index.build_node(&mut items, 0);
+		return index;
+	}
+
+	fn build_node(&mut self, items: &mut [(usize, Vec3)], depth: usize) -> Option<usize>
+	{
+		if items.is_empty() {
+			return None;
+		}
+
+		let axis = depth % 3;
+		items.sort_by(|a, b| a.1[axis].total_cmp(&b.1[axis]).then(a.0.cmp(&b.0)));
+		let mid = items.len() / 2;
+		let (biome, point) = items[mid];
+
+		let id = self.nodes.len();
+		self.nodes.push(BiomeIndexNode {
+			point,
+			biome,
+			axis,
+			left: None,
+			right: None,
+		});
+
+		let (left, rest) = items.split_at_mut(mid);
+		let left = self.build_node(left, depth + 1);
+		let right = self.build_node(&mut rest[1..], depth + 1);
+		self.nodes[id].left = left;
+		self.nodes[id].right = right;
+
+		return Some(id);
+	}
+
+	pub fn len(&self) -> usize
+	{
+		return self.nodes.len();
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		return self.nodes.is_empty();
+	}
+
+	/// Finds the index of the biome closest to `point`.
+	///
+	/// Ties are resolved in favour of the lowest biome index, matching a linear scan.
+	pub fn nearest(&self, point: Vec3) -> Option<usize>
+	{
+		let root = self.root?;
+		let mut best = (f32::INFINITY, usize::MAX);
+		self.search(root, point, &mut best);
+		return Some(best.1);
+	}
+
+	fn search(&self, node_id: usize, point: Vec3, best: &mut (f32, usize))
+	{
+		let node = &self.nodes[node_id];
+		let d = node.point.distance_squared(point);
+		if d < best.0 || (d == best.0 && node.biome < best.1) {
+			*best = (d, node.biome);
+		}
+
+		let delta = point[node.axis] - node.point[node.axis];
+		let (near, far) = if delta < 0. {
+			(node.left, node.right)
+		} else {
+			(node.right, node.left)
+		};
+
+		if let Some(near) = near {
+			self.search(near, point, best);
+		}
+		if let Some(far) = far
+			&& delta * delta <= best.0
+		{
+			self.search(far, point, best);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn linear_nearest(points: &[Vec3], point: Vec3) -> usize
+	{
+		let mut biome = 0;
+		let mut dist = f32::INFINITY;
+		for (i, p) in points.iter().enumerate() {
+			let d = (*p - point).length();
+			if d < dist {
+				biome = i;
+				dist = d;
+			}
+		}
+		return biome;
+	}
+
+	fn test_points(count: usize) -> Vec<Vec3>
+	{
+		return (0..count)
+			.map(|i| {
+				let f = i as f32;
+				Vec3::new((f * 37.3) % 100., (f * 71.9) % 100., (f * 13.7) % 100.)
+			})
+			.collect();
+	}
+
+	#[test]
+	fn matches_linear_scan()
+	{
+		let points = test_points(40);
+		let index = BiomeIndex::new(&points);
+		assert_eq!(index.len(), points.len());
+
+		for x in 0..10 {
+			for y in 0..10 {
+				for z in 0..10 {
+					let p = Vec3::new(x as f32 * 11., y as f32 * 11., z as f32 * 11.);
+					assert_eq!(index.nearest(p), Some(linear_nearest(&points, p)), "Mismatch at {}", p);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn duplicate_points_prefer_lowest_index()
+	{
+		let points = vec![Vec3::splat(50.), Vec3::splat(10.), Vec3::splat(50.)];
+		let index = BiomeIndex::new(&points);
+		assert_eq!(index.nearest(Vec3::splat(49.)), Some(0));
+	}
+
+	#[test]
+	fn empty_index()
+	{
+		let index = BiomeIndex::new(&[]);
+		assert!(index.is_empty());
+		assert_eq!(index.nearest(Vec3::ZERO), None);
+	}
+}
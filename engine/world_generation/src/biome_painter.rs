@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy::render::render_resource::encase::rts_array::Length;
 
-use crate::{biome_asset::BiomeAsset, mapping::biome_map::BiomeData};
+use crate::{biome_asset::BiomeAsset, biome_index::BiomeIndex, biome_rules::BiomeRules, mapping::biome_map::BiomeData};
 use bevy_asset_loader::prelude::*;
 
 #[derive(AssetCollection, Debug, Clone, Resource)]
@@ -9,6 +9,9 @@ pub struct BiomePainterAsset
 {
 	#[asset(key = "biomes", collection(typed))]
 	pub biomes: Vec<Handle<BiomeAsset>>,
+	/// Biomes of the content packs in load order, they replace biomes with the same key
+	#[asset(key = "pack_biomes", collection(typed))]
+	pub pack_biomes: Vec<Handle<BiomeAsset>>,
 }
 
 impl BiomePainterAsset
@@ -31,14 +34,28 @@ impl BiomePainterAsset
 		return biome;
 	}
 
-	pub fn build(&self, assets: &Assets<BiomeAsset>) -> BiomePainter
+	pub fn build(&self, assets: &Assets<BiomeAsset>, asset_server: &AssetServer) -> BiomePainter
 	{
-		let mut biomes = Vec::with_capacity(self.biomes.len());
-		for b in &self.biomes {
-			let asset = assets.get(b.id()).unwrap();
-			biomes.push(asset.clone());
+		let mut biomes: Vec<BiomeAsset> = Vec::with_capacity(self.biomes.len());
+		for b in self.biomes.iter().chain(&self.pack_biomes) {
+			let mut asset = assets.get(b.id()).unwrap().clone();
+			if asset.identifier.is_empty() {
+				//Fall back to the file name without extensions, `desert.biome.ron` is `desert`
+				asset.identifier = asset_server
+					.get_path(b.id())
+					.and_then(|p| p.path().file_name().map(|n| n.to_string_lossy().to_string()))
+					.and_then(|n| n.split('.').next().map(|n| n.to_string()))
+					.unwrap_or_else(|| asset.name.clone());
+			}
+			match biomes.iter().position(|other| other.identifier == asset.identifier) {
+				Some(index) => {
+					info!("A content pack overrides the biome '{}'", asset.identifier);
+					biomes[index] = asset;
+				}
+				None => biomes.push(asset),
+			}
 		}
-		return BiomePainter { biomes };
+		return BiomePainter::new(biomes);
 	}
 }
 
@@ -46,42 +63,31 @@ impl BiomePainterAsset
 pub struct BiomePainter
 {
 	pub biomes: Vec<BiomeAsset>,
+	pub rules: BiomeRules,
+	index: BiomeIndex,
 }
 
 impl BiomePainter
 {
-	pub fn sample_biome(&self, data: &BiomeData) -> &BiomeAsset
+	pub fn new(biomes: Vec<BiomeAsset>) -> Self
 	{
-		assert!(self.biomes.length() != 0, "There are no biomes");
-		let mut biome = &self.biomes[0];
-		let mut dist = f32::INFINITY;
-
-		for b in &self.biomes {
-			let d = b.distance(data.into());
-			if d < dist {
-				biome = b;
-				dist = d;
-			}
-		}
+		let points: Vec<Vec3> = biomes.iter().map(|b| b.climate()).collect();
+		return BiomePainter {
+			index: BiomeIndex::new(&points),
+			rules: BiomeRules::new(&biomes),
+			biomes,
+		};
+	}
 
-		return biome;
+	pub fn sample_biome(&self, data: &BiomeData) -> &BiomeAsset
+	{
+		return &self.biomes[self.sample_biome_index(data)];
 	}
 
 	pub fn sample_biome_index(&self, data: &BiomeData) -> usize
 	{
 		assert!(self.biomes.length() != 0, "There are no biomes");
-		let mut biome = 0;
-		let mut dist = f32::INFINITY;
-
-		for i in 0..self.biomes.len() {
-			let d = self.biomes[i].distance(data.into());
-			if d < dist {
-				biome = i;
-				dist = d;
-			}
-		}
-
-		return biome;
+		return self.index.nearest(data.into()).expect("Biome index is out of date");
 	}
 }
 
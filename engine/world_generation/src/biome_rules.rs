@@ -0,0 +1,288 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+	biome_asset::BiomeAsset,
+	mapping::{
+		biome_map::BiomeMap,
+		biome_regions::{BiomeRegionGraph, get_tile_neighbors},
+	},
+};
+
+/// Upper bound on how many times small regions are merged, merging can create new small regions
+const MAX_MERGE_PASSES: usize = 8;
+
+/// Adjacency constraints between biomes, resolved from the names used in [`BiomeAsset`]
+#[derive(Clone, Default, Debug)]
+pub struct BiomeRules
+{
+	/// `allowed[a][b]` is true when biome `a` accepts `b` as a neighbor
+	allowed: Vec<Vec<bool>>,
+	/// `transitions[a][b]` is the biome and width placed on `a`'s side of a border with `b`
+	transitions: Vec<Vec<Option<(usize, usize)>>>,
+	min_region_size: Vec<usize>,
+}
+
+impl BiomeRules
+{
+	pub fn new(biomes: &[BiomeAsset]) -> Self
+	{
+		let count = biomes.len();
+		let names: HashMap<&str, usize> = biomes.iter().enumerate().map(|(i, b)| (b.name.as_str(), i)).collect();
+		let lookup = |owner: &BiomeAsset, name: &String| -> Option<usize> {
+			let id = names.get(name.as_str()).copied();
+			if id.is_none() {
+				warn!("Biome '{}' references unknown biome '{}'", owner.name, name);
+			}
+			return id;
+		};
+
+		let mut rules = BiomeRules {
+			allowed: vec![vec![true; count]; count],
+			transitions: vec![vec![None; count]; count],
+			min_region_size: biomes.iter().map(|b| b.min_region_size).collect(),
+		};
+
+		for (a, biome) in biomes.iter().enumerate() {
+			if let Some(allowed) = &biome.allowed_neighbors {
+				rules.allowed[a].fill(false);
+				rules.allowed[a][a] = true;
+				for name in allowed {
+					if let Some(b) = lookup(biome, name) {
+						rules.allowed[a][b] = true;
+					}
+				}
+			}
+			for transition in &biome.transitions {
+				if let Some(b) = lookup(biome, &transition.neighbor)
+					&& let Some(t) = lookup(biome, &transition.biome)
+				{
+					rules.transitions[a][b] = Some((t, transition.width.max(1)));
+				}
+			}
+		}
+
+		return rules;
+	}
+
+	pub fn is_allowed(&self, a: usize, b: usize) -> bool
+	{
+		return a == b || (self.allowed[a][b] && self.allowed[b][a]);
+	}
+
+	pub fn get_transition(&self, a: usize, b: usize) -> Option<(usize, usize)>
+	{
+		return self.transitions[a][b];
+	}
+
+	pub fn get_min_region_size(&self, biome: usize) -> usize
+	{
+		return self.min_region_size[biome];
+	}
+
+	/// Merges undersized regions, places transition biomes and builds the final region graph.
+	///
+	/// Tiles that change biome lose their blend weights, so this should run before [`BiomeMap::blend`].
+	pub fn apply(&self, map: &mut BiomeMap) -> BiomeRegionGraph
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Apply Biome Rules").entered();
+		let w = map.width;
+		let h = map.height;
+		let mut ids = map.get_biome_ids();
+
+		self.merge_small_regions(&mut ids, w, h);
+		self.place_transitions(&mut ids, w, h);
+
+		let mut graph = BiomeRegionGraph::build(&ids, w, h);
+		for region in &graph.regions {
+			for edge in &region.neighbors {
+				let other = &graph.regions[edge.region];
+				if region.id < other.id && !self.is_allowed(region.biome, other.biome) {
+					graph.violations.push((region.id, other.id));
+				}
+			}
+		}
+		if !graph.violations.is_empty() {
+			warn!(
+				"{} biome borders break adjacency rules and have no transition biome",
+				graph.violations.len()
+			);
+		}
+
+		map.set_biome_ids(&ids);
+		return graph;
+	}
+
+	fn merge_small_regions(&self, ids: &mut [usize], width: usize, height: usize)
+	{
+		if self.min_region_size.iter().all(|s| *s <= 1) {
+			return;
+		}
+		for _ in 0..MAX_MERGE_PASSES {
+			let graph = BiomeRegionGraph::build(ids, width, height);
+			let mut order: Vec<_> = graph.regions.iter().collect();
+			order.sort_by_key(|r| r.area);
+
+			let mut targets = vec![None; graph.regions.len()];
+			let mut locked = vec![false; graph.regions.len()];
+			for region in order {
+				if locked[region.id] || region.area >= self.get_min_region_size(region.biome) {
+					continue;
+				}
+				let target = region
+					.neighbors
+					.iter()
+					.filter(|e| targets[e.region].is_none())
+					.max_by_key(|e| (e.border, graph.regions[e.region].area));
+				if let Some(edge) = target {
+					targets[region.id] = Some(graph.regions[edge.region].biome);
+					locked[region.id] = true;
+					locked[edge.region] = true;
+				}
+			}
+
+			if targets.iter().all(|t| t.is_none()) {
+				return;
+			}
+			for (tile, id) in ids.iter_mut().enumerate() {
+				if let Some(biome) = targets[graph.tile_regions[tile]] {
+					*id = biome;
+				}
+			}
+		}
+	}
+
+	fn place_transitions(&self, ids: &mut [usize], width: usize, height: usize)
+	{
+		let source = ids.to_vec();
+		let mut depth = vec![usize::MAX; ids.len()];
+		let mut open = VecDeque::new();
+
+		for tile in 0..source.len() {
+			let a = source[tile];
+			for n in get_tile_neighbors(tile, width, height) {
+				let b = source[n];
+				if a == b || self.is_allowed(a, b) {
+					continue;
+				}
+				if let Some((t, w)) = self.get_transition(a, b) {
+					ids[tile] = t;
+					depth[tile] = 1;
+					if w > 1 {
+						open.push_back((tile, a, t, w));
+					}
+					break;
+				}
+			}
+		}
+
+		//Grow the transition into its own biome up to the requested width
+		while let Some((tile, a, t, w)) = open.pop_front() {
+			let d = depth[tile] + 1;
+			for n in get_tile_neighbors(tile, width, height) {
+				if source[n] != a || depth[n] <= d {
+					continue;
+				}
+				ids[n] = t;
+				depth[n] = d;
+				if d < w {
+					open.push_back((n, a, t, w));
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use hex::prelude::*;
+
+	use super::*;
+	use crate::{biome_asset::BiomeTransition, mapping::biome_map::BiomeChunk, prelude::NoiseConfig};
+
+	fn biome(name: &str) -> BiomeAsset
+	{
+		return BiomeAsset {
+			identifier: name.into(),
+			moisture: 0.,
+			temperature: 0.,
+			continentality: 0.,
+			name: name.into(),
+			tile_mapper: default(),
+			tile_mapper_path: default(),
+			noise: NoiseConfig::default(),
+			allowed_neighbors: None,
+			transitions: Vec::new(),
+			min_region_size: 0,
+		};
+	}
+
+	fn create_map(ids: impl Fn(usize, usize) -> usize) -> BiomeMap
+	{
+		let mut map = BiomeMap::new(UVec2::ONE, 3);
+		map.chunks.push(BiomeChunk {
+			offset: UVec2::ZERO,
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		});
+		let tiles: Vec<usize> = (0..Chunk::AREA)
+			.map(|i| ids(i % Chunk::SIZE, i / Chunk::SIZE))
+			.collect();
+		map.set_biome_ids(&tiles);
+		return map;
+	}
+
+	#[test]
+	fn transition_between_disallowed_biomes()
+	{
+		let mut ocean = biome("ocean");
+		ocean.allowed_neighbors = Some(vec!["beach".into()]);
+		let mut forest = biome("forest");
+		forest.transitions.push(BiomeTransition {
+			neighbor: "ocean".into(),
+			biome: "beach".into(),
+			width: 2,
+		});
+		let rules = BiomeRules::new(&[ocean, forest, biome("beach")]);
+		assert!(!rules.is_allowed(0, 1));
+		assert!(rules.is_allowed(0, 2));
+
+		let mut map = create_map(|x, _| if x < Chunk::SIZE / 2 { 0 } else { 1 });
+		let graph = rules.apply(&mut map);
+
+		assert!(graph.violations.is_empty(), "Ocean still touches forest");
+		assert_eq!(graph.regions.len(), 3);
+		assert_eq!(map.get_biome_id(Chunk::SIZE / 2 - 1, 10), 0);
+		assert_eq!(map.get_biome_id(Chunk::SIZE / 2 + 1, 10), 2);
+		assert_eq!(map.get_biome_id(Chunk::SIZE / 2 + 4, 10), 1);
+	}
+
+	#[test]
+	fn report_violations()
+	{
+		let mut ocean = biome("ocean");
+		ocean.allowed_neighbors = Some(Vec::new());
+		let rules = BiomeRules::new(&[ocean, biome("forest")]);
+
+		let mut map = create_map(|x, _| if x < Chunk::SIZE / 2 { 0 } else { 1 });
+		let graph = rules.apply(&mut map);
+		assert_eq!(graph.violations, vec![(0, 1)]);
+	}
+
+	#[test]
+	fn merge_small_regions()
+	{
+		let mut forest = biome("forest");
+		forest.min_region_size = 5;
+		let rules = BiomeRules::new(&[biome("plains"), forest]);
+
+		let mut map = create_map(|x, y| if (10..12).contains(&x) && y == 10 { 1 } else { 0 });
+		let graph = rules.apply(&mut map);
+		assert_eq!(graph.regions.len(), 1);
+		assert_eq!(graph.regions[0].area, Chunk::AREA);
+		assert_eq!(map.get_biome_id(10, 10), 0);
+	}
+}
@@ -8,7 +8,7 @@ use noise::{NoiseFn, Simplex, SuperSimplex};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 use crate::biome_painter::BiomePainter;
-use crate::mapping::biome_map::{BiomeChunk, BiomeData, BiomeMap};
+use crate::mapping::biome_map::{BiomeChunk, BiomeData, BiomeMap, BiomeWeights};
 use crate::prelude::*;
 
 pub fn generate_heightmap(cfg: &GenerationConfig, seed: u32, painter: &BiomePainter) -> (Map, BiomeMap)
@@ -36,18 +36,18 @@ pub fn generate_heightmap(cfg: &GenerationConfig, seed: u32, painter: &BiomePain
 		}
 	}
 
-	return (
-		Map {
-			chunks,
-			height: cfg.size.y as usize,
-			width: cfg.size.x as usize,
-			sealevel: cfg.sea_level as f32,
-			min_level: min,
-			max_level: max,
-			biome_count: painter.biomes.len(),
-		},
-		biomes,
-	);
+	let mut map = Map {
+		chunks,
+		height: cfg.size.y as usize,
+		width: cfg.size.x as usize,
+		sealevel: cfg.sea_level as f32,
+		min_level: min,
+		max_level: max,
+		biome_count: painter.biomes.len(),
+		tile_classes: default(),
+	};
+	map.build_tile_classes();
+	return (map, biomes);
 }
 
 pub fn generate_biomes(cfg: &GenerationConfig, seed: u32, biome_painter: &BiomePainter) -> BiomeMap
@@ -61,6 +61,7 @@ pub fn generate_biomes(cfg: &GenerationConfig, seed: u32, biome_painter: &BiomeP
 			})
 		})
 		.collect();
+	map.regions = biome_painter.rules.apply(&mut map);
 	map.blend(cfg.biome_blend);
 	return map;
 }
@@ -116,8 +117,7 @@ pub fn generate_biome_chunk(
 				temperature: temperature.clamp(0., 100.),
 				continentality: continentality.clamp(0., 100.),
 			};
-			let mut b = vec![0.; biome_painter.biomes.len()];
-			b[biome_painter.sample_biome_index(&data)] = 1.;
+			let b = BiomeWeights::single(biome_painter.sample_biome_index(&data));
 
 			chunk.data[x + z * Chunk::SIZE] = data;
 			chunk.tiles.push(b);
@@ -172,8 +172,8 @@ pub fn generate_chunk(
 			let biome_data = biome_chunk.get_biome_data(x, z);
 			let biome_blend = biome_chunk.get_biome(x, z);
 			let mut sample = 0.;
-			for (i, blend) in biome_blend.iter().enumerate() {
-				if *blend == 0. {
+			for (i, blend) in biome_blend.iter() {
+				if blend == 0. {
 					continue;
 				}
 				let biome = &biome_painter.biomes[i];
@@ -1,10 +1,13 @@
 pub mod biome_asset;
+pub mod biome_index;
 pub mod biome_painter;
+pub mod biome_rules;
 pub mod consts;
 pub mod generators;
 pub mod heightmap;
 pub mod mapping;
 pub mod prelude;
 pub mod states;
+pub mod texture_atlas;
 pub mod tile_manager;
 pub mod tile_mapper;
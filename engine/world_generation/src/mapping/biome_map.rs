@@ -3,10 +3,19 @@ use bevy::{
 	prelude::*,
 };
 use noise::NoiseFn;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::prelude::*;
 
 use hex::prelude::*;
 
+use super::biome_regions::BiomeRegionGraph;
+
+/// Maximum number of biomes that can be blended together on a single tile
+pub const MAX_BIOME_WEIGHTS: usize = 4;
+/// Number of separable box blur passes used by [`BiomeMap::blend`]
+const BLEND_PASSES: usize = 3;
+/// Blended weights below this are dropped
+const MIN_BIOME_WEIGHT: f32 = 0.0001;
+
 #[derive(Clone, Resource)]
 pub struct BiomeMap
 {
@@ -15,6 +24,7 @@ pub struct BiomeMap
 	pub size: UVec2,
 	pub biome_count: usize,
 	pub chunks: Vec<BiomeChunk>,
+	pub regions: BiomeRegionGraph,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -53,76 +63,124 @@ impl BiomeMap
 			width: size.x as usize * Chunk::SIZE,
 			biome_count,
 			chunks: Vec::with_capacity(len),
+			regions: BiomeRegionGraph::default(),
 		};
 	}
 
+	/// Gets the dominant biome of every tile, row major
+	pub fn get_biome_ids(&self) -> Vec<usize>
+	{
+		let mut ids = vec![0; self.width * self.height];
+		for y in 0..self.height {
+			for x in 0..self.width {
+				ids[x + y * self.width] = self.get_biome_id(x, y);
+			}
+		}
+		return ids;
+	}
+
+	/// Sets the biome of every tile, row major. Tiles that change biome are no longer blended
+	pub fn set_biome_ids(&mut self, ids: &[usize])
+	{
+		assert!(
+			ids.len() == self.width * self.height,
+			"Biome ids do not match the map size"
+		);
+		let w = self.width;
+		self.chunks.par_iter_mut().for_each(|chunk| {
+			let ox = chunk.offset.x as usize * Chunk::SIZE;
+			let oy = chunk.offset.y as usize * Chunk::SIZE;
+			for y in 0..Chunk::SIZE {
+				for x in 0..Chunk::SIZE {
+					let id = ids[(x + ox) + (y + oy) * w];
+					if chunk.get_biome_id(x, y) != id || chunk.get_biome(x, y).is_empty() {
+						chunk.tiles[x + y * Chunk::SIZE] = BiomeWeights::single(id);
+					}
+				}
+			}
+		});
+	}
+
+	/// Blurs the biome weights so that biomes fade into each other.
+	///
+	/// `count` is the number of 3x3 box blur passes to approximate. Small counts are applied exactly, larger counts
+	/// are approximated with [`BLEND_PASSES`] separable box blurs of an equivalent radius.
 	pub fn blend(&mut self, count: usize)
 	{
 		assert!(count != 0, "Count cannot be 0");
-		for _ in 0..count {
-			self.blend_once();
+		let (passes, radius) = Self::get_blend_kernel(count);
+		let w = self.width;
+		let h = self.height;
+
+		let mut blended = vec![BiomeWeights::default(); w * h];
+		let mut plane = vec![0.; w * h];
+		let mut scratch = vec![0.; w * h];
+		for biome in 0..self.biome_count {
+			if !self.fill_biome_plane(biome, &mut plane) {
+				continue;
+			}
+			for _ in 0..passes {
+				box_blur_rows(&plane, &mut scratch, w, radius);
+				box_blur_columns(&scratch, &mut plane, w, h, radius);
+			}
+			blended.par_iter_mut().zip(plane.par_iter()).for_each(|(weights, v)| {
+				if *v > MIN_BIOME_WEIGHT {
+					weights.insert(biome, *v);
+				}
+			});
 		}
+
+		self.chunks.par_iter_mut().for_each(|chunk| {
+			let ox = chunk.offset.x as usize * Chunk::SIZE;
+			let oy = chunk.offset.y as usize * Chunk::SIZE;
+			for y in 0..Chunk::SIZE {
+				for x in 0..Chunk::SIZE {
+					let mut weights = blended[(x + ox) + (y + oy) * w];
+					weights.normalize();
+					chunk.tiles[x + y * Chunk::SIZE] = weights;
+				}
+			}
+		});
 	}
 
-	fn blend_once(&mut self)
+	/// Gets the number of passes and the radius of the box blur used to approximate `count` 3x3 blurs
+	fn get_blend_kernel(count: usize) -> (usize, usize)
 	{
-		let c: Vec<BiomeChunk> = (0..self.chunks.len())
-			.into_par_iter()
-			.map(|i| &self.chunks[i])
-			.map(|chunk| {
-				let tiles: Vec<_> = (0..Chunk::SIZE)
-					.into_par_iter()
-					.map(|y| {
-						let mut new_tiles = Vec::with_capacity(self.width);
-						for x in 0..Chunk::SIZE {
-							let tx = x as u32 + chunk.offset.x * Chunk::SIZE as u32;
-							let ty = y as u32 + chunk.offset.y * Chunk::SIZE as u32;
-							let kernel = self.get_kernel(tx as i32, ty as i32);
-
-							let r = kernel
-								.iter()
-								.filter_map(|f| *f)
-								.fold(vec![0.; self.biome_count], |a, b| {
-									return a.iter().zip(b).map(|v| v.0 + v.1).collect();
-								});
-
-							let sum: f32 = r.iter().sum();
-							if sum == 0. {
-								new_tiles.push(vec![0.; self.biome_count]);
-								continue;
-							}
-							new_tiles.push(r.iter().map(|f| f / sum).collect());
-						}
-						return new_tiles;
-					})
-					.flatten()
-					.collect();
-				return BiomeChunk {
-					offset: chunk.offset,
-					tiles,
-					data: chunk.data,
-				};
-			})
-			.collect();
-		self.chunks = c;
+		if count <= BLEND_PASSES {
+			return (count, 1);
+		}
+		//Each 3x3 pass adds a variance of 2/3, a box of radius r adds r(r + 1)/3
+		let variance = count as f32 * 2. / 3.;
+		let r_r1 = variance * 3. / BLEND_PASSES as f32;
+		let r = ((1. + 4. * r_r1).sqrt() - 1.) / 2.;
+		return (BLEND_PASSES, (r.round() as usize).max(1));
 	}
 
-	fn get_kernel(&self, x: i32, y: i32) -> [Option<&Vec<f32>>; 9]
+	/// Writes the weight of a single biome for every tile into `plane`, returns false when the biome is not present
+	fn fill_biome_plane(&self, biome: usize, plane: &mut [f32]) -> bool
 	{
-		return [
-			self.get_biome(x - 1, y - 1),
-			self.get_biome(x, y - 1),
-			self.get_biome(x + 1, y - 1),
-			self.get_biome(x - 1, y),
-			self.get_biome(x, y),
-			self.get_biome(x + 1, y),
-			self.get_biome(x - 1, y + 1),
-			self.get_biome(x, y + 1),
-			self.get_biome(x + 1, y + 1),
-		];
+		let row_len = self.width;
+		let size_x = self.size.x as usize;
+		let chunks = &self.chunks;
+		return plane
+			.par_chunks_mut(row_len)
+			.enumerate()
+			.map(|(y, row)| {
+				let cy = y / Chunk::SIZE;
+				let ly = y - cy * Chunk::SIZE;
+				let mut found = false;
+				for (x, v) in row.iter_mut().enumerate() {
+					let cx = x / Chunk::SIZE;
+					let chunk = &chunks[cx + cy * size_x];
+					*v = chunk.get_biome(x - cx * Chunk::SIZE, ly).get(biome);
+					found |= *v > 0.;
+				}
+				return found;
+			})
+			.reduce(|| false, |a, b| a || b);
 	}
 
-	pub fn get_biome(&self, x: i32, y: i32) -> Option<&Vec<f32>>
+	pub fn get_biome(&self, x: i32, y: i32) -> Option<&BiomeWeights>
 	{
 		if x < 0 || y < 0 {
 			return None;
@@ -169,17 +227,141 @@ impl BiomeMap
 	}
 }
 
+/// Box blurs each row of `src` into `dst`, tiles outside of the map are ignored
+fn box_blur_rows(src: &[f32], dst: &mut [f32], width: usize, radius: usize)
+{
+	dst.par_chunks_mut(width)
+		.zip(src.par_chunks(width))
+		.for_each(|(out, row)| {
+			let mut sum: f32 = row.iter().take(radius).sum();
+			for x in 0..width {
+				if x + radius < width {
+					sum += row[x + radius];
+				}
+				if x > radius {
+					sum -= row[x - radius - 1];
+				}
+				let count = (x + radius).min(width - 1) + 1 - x.saturating_sub(radius);
+				out[x] = (sum / count as f32).max(0.);
+			}
+		});
+}
+
+/// Box blurs each column of `src` into `dst`, tiles outside of the map are ignored
+fn box_blur_columns(src: &[f32], dst: &mut [f32], width: usize, height: usize, radius: usize)
+{
+	dst.par_chunks_mut(width).enumerate().for_each(|(y, out)| {
+		let start = y.saturating_sub(radius);
+		let end = (y + radius).min(height - 1);
+		out.fill(0.);
+		for row in src[(start * width)..((end + 1) * width)].chunks(width) {
+			for (o, v) in out.iter_mut().zip(row) {
+				*o += v;
+			}
+		}
+		let count = (end + 1 - start) as f32;
+		for o in out.iter_mut() {
+			*o /= count;
+		}
+	});
+}
+
+/// Sparse biome blend weights for a single tile, only the strongest [`MAX_BIOME_WEIGHTS`] biomes are kept
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct BiomeWeights
+{
+	ids: [usize; MAX_BIOME_WEIGHTS],
+	weights: [f32; MAX_BIOME_WEIGHTS],
+	len: usize,
+}
+
+impl BiomeWeights
+{
+	pub fn single(biome: usize) -> Self
+	{
+		let mut w = BiomeWeights::default();
+		w.insert(biome, 1.);
+		return w;
+	}
+
+	/// Adds a biome weight, replacing the weakest biome when full
+	pub fn insert(&mut self, biome: usize, weight: f32)
+	{
+		if let Some(i) = self.ids[..self.len].iter().position(|b| *b == biome) {
+			self.weights[i] = weight;
+			return;
+		}
+		if self.len < MAX_BIOME_WEIGHTS {
+			self.ids[self.len] = biome;
+			self.weights[self.len] = weight;
+			self.len += 1;
+			return;
+		}
+		let (min, min_weight) = self
+			.weights
+			.iter()
+			.copied()
+			.enumerate()
+			.min_by(|a, b| a.1.total_cmp(&b.1))
+			.unwrap();
+		if weight > min_weight {
+			self.ids[min] = biome;
+			self.weights[min] = weight;
+		}
+	}
+
+	pub fn get(&self, biome: usize) -> f32
+	{
+		return self.iter().find(|(b, _)| *b == biome).map_or(0., |(_, w)| w);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (usize, f32)> + '_
+	{
+		return self.ids[..self.len]
+			.iter()
+			.copied()
+			.zip(self.weights[..self.len].iter().copied());
+	}
+
+	pub fn len(&self) -> usize
+	{
+		return self.len;
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		return self.len == 0;
+	}
+
+	pub fn sum(&self) -> f32
+	{
+		return self.weights[..self.len].iter().sum();
+	}
+
+	/// Scales the weights so they add up to 1
+	pub fn normalize(&mut self)
+	{
+		let sum = self.sum();
+		if sum == 0. {
+			return;
+		}
+		for w in self.weights[..self.len].iter_mut() {
+			*w /= sum;
+		}
+	}
+}
+
 #[derive(Clone)]
 pub struct BiomeChunk
 {
-	pub tiles: Vec<Vec<f32>>,
+	pub tiles: Vec<BiomeWeights>,
 	pub offset: UVec2,
 	pub data: [BiomeData; Chunk::AREA],
 }
 
 impl BiomeChunk
 {
-	pub fn get_biome(&self, x: usize, y: usize) -> &Vec<f32>
+	pub fn get_biome(&self, x: usize, y: usize) -> &BiomeWeights
 	{
 		return &self.tiles[x + y * Chunk::SIZE];
 	}
@@ -194,9 +376,9 @@ impl BiomeChunk
 		let b = self.get_biome(x, y);
 		let mut max = 0.;
 		let mut idx = 0;
-		for (i, blend) in b.iter().enumerate() {
-			if *blend > max {
-				max = *blend;
+		for (i, blend) in b.iter() {
+			if blend > max || (blend == max && i < idx) {
+				max = blend;
 				idx = i;
 			}
 		}
@@ -208,12 +390,12 @@ impl BiomeChunk
 		let mut cur_id = self.get_biome_id(x, y);
 		let b = self.get_biome(x, y);
 		let n = (noise.get([x as f64 / scale, y as f64 / scale]) as f32 - 0.5) / 2.0;
-		let mut max = b[cur_id] + n;
-		for (i, blend) in b.iter().enumerate() {
-			if *blend == 0. {
+		let mut max = b.get(cur_id) + n;
+		for (i, blend) in b.iter() {
+			if blend == 0. {
 				continue;
 			}
-			if *blend > max {
+			if blend > max {
 				max = blend + n;
 				cur_id = i;
 			}
@@ -238,23 +420,60 @@ mod tests
 
 		for y in 0..h {
 			for x in 0..w {
-				let mut b = vec![0.; biome.biome_count];
 				let idx = (x + y) % biome.biome_count;
-				b[idx] = 1.;
-				biome.chunks.push(generate_chunk(x, y, b));
+				biome.chunks.push(generate_chunk(x, y, idx));
 			}
 		}
 
 		biome.blend(8);
 		assert!(biome.chunks.iter().all(|f| f.tiles.len() == Chunk::AREA), "Data Lost");
+		assert!(
+			biome
+				.chunks
+				.iter()
+				.flat_map(|c| c.tiles.iter())
+				.all(|t| (t.sum() - 1.).abs() < 0.001),
+			"Weights are not normalized"
+		);
+	}
+
+	#[test]
+	fn biome_blend_single_pass()
+	{
+		let mut biome = BiomeMap::new(UVec2::new(2, 1), 2);
+		biome.chunks.push(generate_chunk(0, 0, 0));
+		biome.chunks.push(generate_chunk(1, 0, 1));
+
+		biome.blend(1);
+		let edge = biome.get_biome(Chunk::SIZE as i32 - 1, 10).unwrap();
+		assert!((edge.get(0) - 2. / 3.).abs() < 0.001, "Unexpected blend {:?}", edge);
+		assert!((edge.get(1) - 1. / 3.).abs() < 0.001, "Unexpected blend {:?}", edge);
+		let inner = biome.get_biome(10, 10).unwrap();
+		assert_eq!(inner.get(0), 1.);
+		assert_eq!(inner.len(), 1);
+	}
+
+	#[test]
+	fn biome_weights_keep_strongest()
+	{
+		let mut w = BiomeWeights::default();
+		for i in 0..MAX_BIOME_WEIGHTS {
+			w.insert(i, (i + 1) as f32);
+		}
+		w.insert(10, 0.5);
+		assert_eq!(w.get(10), 0.);
+		w.insert(11, 10.);
+		assert_eq!(w.get(11), 10.);
+		assert_eq!(w.get(0), 0.);
+		assert_eq!(w.len(), MAX_BIOME_WEIGHTS);
 	}
 
-	fn generate_chunk(x: usize, y: usize, biome: Vec<f32>) -> BiomeChunk
+	fn generate_chunk(x: usize, y: usize, biome: usize) -> BiomeChunk
 	{
 		let chunk = BiomeChunk {
 			offset: UVec2::new(x as u32, y as u32),
 			data: [BiomeData::default(); Chunk::AREA],
-			tiles: (0..Chunk::AREA).into_iter().map(|_| biome.clone()).collect(),
+			tiles: vec![BiomeWeights::single(biome); Chunk::AREA],
 		};
 
 		return chunk;
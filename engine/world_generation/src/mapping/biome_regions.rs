@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use hex::prelude::*;
+
+/// Connected areas of a single biome and how they border each other
+#[derive(Clone, Default, Debug)]
+pub struct BiomeRegionGraph
+{
+	pub regions: Vec<BiomeRegion>,
+	/// Region index of every tile, row major
+	pub tile_regions: Vec<usize>,
+	pub width: usize,
+	pub height: usize,
+	/// Pairs of regions whose biomes are not allowed to border each other
+	pub violations: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BiomeRegion
+{
+	pub id: usize,
+	pub biome: usize,
+	pub area: usize,
+	pub neighbors: Vec<BiomeRegionEdge>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BiomeRegionEdge
+{
+	pub region: usize,
+	/// Number of tile edges shared with the neighboring region
+	pub border: usize,
+}
+
+impl BiomeRegionGraph
+{
+	/// Flood fills `ids` into connected regions of the same biome
+	pub fn build(ids: &[usize], width: usize, height: usize) -> Self
+	{
+		assert!(ids.len() == width * height, "Biome ids do not match the map size");
+		let mut tile_regions = vec![usize::MAX; ids.len()];
+		let mut regions = Vec::new();
+		let mut open = Vec::new();
+
+		for start in 0..ids.len() {
+			if tile_regions[start] != usize::MAX {
+				continue;
+			}
+			let id = regions.len();
+			let biome = ids[start];
+			let mut area = 0;
+			tile_regions[start] = id;
+			open.push(start);
+			while let Some(tile) = open.pop() {
+				area += 1;
+				for n in get_tile_neighbors(tile, width, height) {
+					if tile_regions[n] == usize::MAX && ids[n] == biome {
+						tile_regions[n] = id;
+						open.push(n);
+					}
+				}
+			}
+			regions.push(BiomeRegion {
+				id,
+				biome,
+				area,
+				neighbors: Vec::new(),
+			});
+		}
+
+		let mut borders: HashMap<(usize, usize), usize> = HashMap::new();
+		for tile in 0..ids.len() {
+			let r = tile_regions[tile];
+			for n in get_tile_neighbors(tile, width, height) {
+				let nr = tile_regions[n];
+				if nr != r {
+					*borders.entry((r, nr)).or_default() += 1;
+				}
+			}
+		}
+		for ((r, nr), border) in borders {
+			regions[r].neighbors.push(BiomeRegionEdge { region: nr, border });
+		}
+		for region in regions.iter_mut() {
+			region.neighbors.sort_by_key(|e| e.region);
+		}
+
+		return BiomeRegionGraph {
+			regions,
+			tile_regions,
+			width,
+			height,
+			violations: Vec::new(),
+		};
+	}
+
+	pub fn get_region(&self, x: usize, y: usize) -> &BiomeRegion
+	{
+		return &self.regions[self.tile_regions[x + y * self.width]];
+	}
+
+	pub fn get_border(&self, a: usize, b: usize) -> usize
+	{
+		return self.regions[a]
+			.neighbors
+			.iter()
+			.find(|e| e.region == b)
+			.map_or(0, |e| e.border);
+	}
+}
+
+/// Gets the row major indices of the tiles bordering `tile`
+pub(crate) fn get_tile_neighbors(tile: usize, width: usize, height: usize) -> impl Iterator<Item = usize>
+{
+	let coord = HexCoord::from_offset_pos(tile % width, tile / width);
+	return coord.get_neighbors().into_iter().filter_map(move |n| {
+		if !n.is_in_bounds(height, width) {
+			return None;
+		}
+		let off = n.to_offset();
+		return Some(off.x as usize + off.y as usize * width);
+	});
+}
@@ -1,7 +1,11 @@
 use bevy::prelude::*;
 use hex::prelude::*;
 
-use super::mesh_chunk::MeshChunkData;
+use super::{
+	mesh_chunk::MeshChunkData,
+	tile_class::{TileClass, TileClassMap},
+};
+use crate::tile_manager::TileAsset;
 
 #[derive(Resource, Clone)]
 pub struct Map
@@ -13,10 +17,15 @@ pub struct Map
 	pub min_level: f32,
 	pub max_level: f32,
 	pub biome_count: usize,
+	/// Cached class of every tile, see [`Map::build_tile_classes`]
+	pub tile_classes: TileClassMap,
 }
 
 impl Map
 {
+	/// How far from the coast `distance_to_land` is calculated, in tiles
+	pub const LAND_DISTANCE_RANGE: usize = 4;
+
 	pub fn get_tile_count(&self) -> usize
 	{
 		return self.get_tile_width() * self.get_tile_height();
@@ -32,6 +41,15 @@ impl Map
 		return self.height * Chunk::SIZE;
 	}
 
+	/// Gets the height of every tile, row major
+	pub fn get_tile_heights(&self) -> Vec<f32>
+	{
+		let width = self.get_tile_width();
+		return (0..self.get_tile_count())
+			.map(|i| self.sample_height(&HexCoord::from_offset_pos(i % width, i / width)))
+			.collect();
+	}
+
 	pub fn get_chunk_mesh_data(&self, chunk_index: usize) -> MeshChunkData
 	{
 		#[cfg(feature = "tracing")]
@@ -43,7 +61,7 @@ impl Map
 			sealevel: self.sealevel,
 			heights: chunk.heights,
 			textures: chunk.textures,
-			distance_to_land: self.get_distance_from_land(chunk.chunk_offset, 4),
+			distance_to_land: self.get_distance_from_land(chunk.chunk_offset, Self::LAND_DISTANCE_RANGE),
 		};
 	}
 
@@ -85,6 +103,18 @@ impl Map
 		return dists;
 	}
 
+	/// Gets the distance in tiles to the closest water tile within `range`, 0 for water tiles
+	pub fn get_distance_to_water(&self, pos: &HexCoord, range: usize) -> Option<usize>
+	{
+		if self.is_underwater(pos) {
+			return Some(0);
+		}
+		if range == 0 {
+			return None;
+		}
+		return self.hex_select_first(pos, range, false, |_t, h, r| (h <= self.sealevel).then_some(r));
+	}
+
 	pub fn get_neighbors(&self, pos: &HexCoord) -> [Option<f32>; 6]
 	{
 		let mut results: [Option<f32>; 6] = [None; 6];
@@ -104,6 +134,38 @@ impl Map
 		return results;
 	}
 
+	/// Gets the largest height difference between a tile and its neighbors
+	pub fn slope_at(&self, pos: &HexCoord) -> f32
+	{
+		let height = self.sample_height(pos);
+		return self
+			.get_neighbors(pos)
+			.iter()
+			.flatten()
+			.fold(0., |slope, n| f32::max(slope, (n - height).abs()));
+	}
+
+	/// Gets the difference between the highest and lowest of `coords`, ignoring tiles out of bounds
+	pub fn max_height_delta(&self, coords: &[HexCoord]) -> f32
+	{
+		let (min, max) = coords
+			.iter()
+			.filter(|c| self.is_in_bounds(c))
+			.map(|c| self.sample_height(c))
+			.fold((f32::MAX, f32::MIN), |(min, max), h| (min.min(h), max.max(h)));
+		if min > max {
+			return 0.;
+		}
+		return max - min;
+	}
+
+	/// How flat the ground under `footprint` is, from 1 for level ground to 0 for a height difference of
+	/// [`TileClass::CLIFF_HEIGHT`] or more
+	pub fn flatness(&self, footprint: &[HexCoord]) -> f32
+	{
+		return 1. - (self.max_height_delta(footprint) / TileClass::CLIFF_HEIGHT).min(1.);
+	}
+
 	pub fn sample_height(&self, pos: &HexCoord) -> f32
 	{
 		assert!(
@@ -131,6 +193,32 @@ impl Map
 		return pos.is_in_bounds(self.height * Chunk::SIZE, self.width * Chunk::SIZE);
 	}
 
+	/// Gets the id of the tile type painted on a tile, see [`crate::tile_manager::TileManager`]
+	pub fn get_tile_type(&self, pos: &HexCoord) -> usize
+	{
+		assert!(
+			self.is_in_bounds(pos),
+			"The provided coordinate is not within the map bounds"
+		);
+
+		let chunk = &self.chunks[pos.to_chunk_index(self.width)];
+		return chunk.tile_types[pos.to_chunk_local_index()];
+	}
+
+	/// Changes the tile type of a tile and the textures it is drawn with, the chunk mesh has to be rebuilt to show it
+	pub fn set_tile_type(&mut self, pos: &HexCoord, tile: &TileAsset)
+	{
+		assert!(
+			self.is_in_bounds(pos),
+			"The provided coordinate is not within the map bounds"
+		);
+
+		let chunk = &mut self.chunks[pos.to_chunk_index(self.width)];
+		let index = pos.to_chunk_local_index();
+		chunk.tile_types[index] = tile.id;
+		chunk.textures[index] = [tile.texture_id, tile.side_texture_id];
+	}
+
 	pub fn get_biome_id(&self, pos: &HexCoord) -> usize
 	{
 		assert!(
@@ -177,14 +265,126 @@ impl Map
 		self.chunks[pos.to_chunk_index(self.width)].heights[pos.to_chunk_local_index()] = height;
 	}
 
+	/// Classifies a tile from its height and neighbors, see [`TileClass::classify`]
+	pub fn get_tile_class(&self, pos: &HexCoord) -> TileClass
+	{
+		let height = self.sample_height(pos);
+		//Skip sampling the neighbors, water only depends on the height
+		if height <= self.sealevel {
+			return TileClass::classify(height, self.sealevel, self.get_peak_level(), &[None; 6]);
+		}
+		return TileClass::classify(height, self.sealevel, self.get_peak_level(), &self.get_neighbors(pos));
+	}
+
+	/// Classifies every tile of a chunk, in chunk local order
+	pub fn get_chunk_tile_classes(&self, chunk_index: usize) -> [TileClass; Chunk::AREA]
+	{
+		let chunk = &self.chunks[chunk_index];
+		let cx = chunk.chunk_offset.x as usize * Chunk::SIZE;
+		let cz = chunk.chunk_offset.y as usize * Chunk::SIZE;
+		let mut classes = [TileClass::default(); Chunk::AREA];
+		for z in 0..Chunk::SIZE {
+			for x in 0..Chunk::SIZE {
+				classes[x + z * Chunk::SIZE] = self.get_tile_class(&HexCoord::from_offset_pos(x + cx, z + cz));
+			}
+		}
+		return classes;
+	}
+
+	/// Classifies every tile, the predicates like [`Map::is_on_land`] read these cached classes
+	pub fn build_tile_classes(&mut self)
+	{
+		self.tile_classes = TileClassMap::build(self);
+	}
+
+	/// Reclassifies the given chunks, needed after their heights or the sea level changed
+	pub fn update_tile_classes(&mut self, chunks: &[usize])
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Update Tile Classes").entered();
+		if self.tile_classes.is_empty() {
+			return;
+		}
+		for &index in chunks {
+			let classes = self.get_chunk_tile_classes(index);
+			self.tile_classes.set_chunk(index, classes);
+		}
+	}
+
+	/// Gets the cached class of a tile, tiles are classified on the fly while the cache is not built
+	pub fn get_cached_tile_class(&self, pos: &HexCoord) -> TileClass
+	{
+		if self.tile_classes.is_empty() {
+			return self.get_tile_class(pos);
+		}
+		return self.tile_classes.get(pos);
+	}
+
+	/// Height above which land tiles are classified as [`TileClass::Peak`]
+	pub fn get_peak_level(&self) -> f32
+	{
+		return self.sealevel + (self.max_level - self.sealevel) * TileClass::PEAK_HEIGHT;
+	}
+
 	pub fn is_underwater(&self, pos: &HexCoord) -> bool
 	{
-		self.sealevel < self.sample_height(pos)
+		return self.get_cached_tile_class(pos).is_water();
 	}
 
 	pub fn is_on_land(&self, pos: &HexCoord) -> bool
 	{
-		self.sealevel >= self.sample_height(pos)
+		return self.get_cached_tile_class(pos).is_land();
+	}
+
+	/// Changes the sea level, returning the tiles that went from land to water or from water to land
+	pub fn set_sealevel(&mut self, sealevel: f32) -> Vec<HexCoord>
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Set Sea Level").entered();
+		let low = self.sealevel.min(sealevel);
+		let high = self.sealevel.max(sealevel);
+		self.sealevel = sealevel;
+		if low == high {
+			return Vec::new();
+		}
+
+		let mut tiles = Vec::new();
+		for chunk in &self.chunks {
+			let cx = chunk.chunk_offset.x as usize * Chunk::SIZE;
+			let cz = chunk.chunk_offset.y as usize * Chunk::SIZE;
+			for z in 0..Chunk::SIZE {
+				for x in 0..Chunk::SIZE {
+					let h = chunk.heights[x + z * Chunk::SIZE];
+					if h > low && h <= high {
+						tiles.push(HexCoord::from_offset_pos(x + cx, z + cz));
+					}
+				}
+			}
+		}
+		return tiles;
+	}
+
+	/// Gets the indices of every chunk that has a tile within `range` of any of `tiles`, sorted
+	pub fn get_chunks_in_range(&self, tiles: &[HexCoord], range: usize) -> Vec<usize>
+	{
+		let max_x = self.get_tile_width() - 1;
+		let max_z = self.get_tile_height() - 1;
+		let mut chunks = Vec::new();
+		for tile in tiles {
+			let pos = tile.to_offset();
+			let min = (pos - range as i32).max(IVec2::ZERO).as_uvec2() / Chunk::SIZE as u32;
+			let max = (pos + range as i32)
+				.min(IVec2::new(max_x as i32, max_z as i32))
+				.as_uvec2() / Chunk::SIZE as u32;
+			for cz in min.y..=max.y {
+				for cx in min.x..=max.x {
+					chunks.push(cx as usize + cz as usize * self.width);
+				}
+			}
+		}
+		chunks.sort_unstable();
+		chunks.dedup();
+		return chunks;
 	}
 
 	pub fn create_crater(&mut self, pos: &HexCoord, radius: usize, depth: f32) -> Vec<(HexCoord, f32)>
@@ -362,3 +562,119 @@ impl Map
 		None
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn create_map() -> Map
+	{
+		//Two chunks side by side, heights rise from 0 to 63 left to right in every chunk
+		let chunks = (0..2)
+			.map(|cx| {
+				let mut chunk = Chunk {
+					chunk_offset: IVec2::new(cx, 0),
+					..default()
+				};
+				for i in 0..Chunk::AREA {
+					chunk.heights[i] = (i % Chunk::SIZE) as f32;
+				}
+				return chunk;
+			})
+			.collect();
+		return Map {
+			chunks,
+			height: 1,
+			width: 2,
+			sealevel: 10.5,
+			min_level: 0.,
+			max_level: 63.,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+	}
+
+	#[test]
+	fn sealevel_change()
+	{
+		let mut map = create_map();
+		let tiles = map.set_sealevel(12.5);
+		assert_eq!(map.sealevel, 12.5);
+		assert_eq!(tiles.len(), 2 * 2 * Chunk::SIZE);
+		assert!(tiles.iter().all(|t| {
+			let h = map.sample_height(t);
+			return h == 11. || h == 12.;
+		}));
+
+		let tiles = map.set_sealevel(10.5);
+		assert_eq!(tiles.len(), 2 * 2 * Chunk::SIZE);
+		assert!(map.set_sealevel(10.7).is_empty());
+	}
+
+	#[test]
+	fn slope()
+	{
+		let mut map = create_map();
+		let pos = HexCoord::from_offset_pos(10, 10);
+		assert_eq!(map.slope_at(&pos), 1.);
+		let footprint = [pos, pos.get_neighbor(0), pos.get_neighbor(1)];
+		assert_eq!(map.max_height_delta(&footprint), 1.);
+		assert_eq!(map.flatness(&footprint), 0.5);
+		assert_eq!(map.flatness(&[pos]), 1.);
+
+		map.set_height(&pos, 20.);
+		assert_eq!(map.slope_at(&pos), 11.);
+		assert_eq!(map.flatness(&footprint), 0.);
+		assert_eq!(map.max_height_delta(&[HexCoord::from_offset_pos(500, 500)]), 0.);
+	}
+
+	#[test]
+	fn water_distance()
+	{
+		let map = create_map();
+		assert_eq!(map.get_distance_to_water(&HexCoord::from_offset_pos(5, 10), 4), Some(0));
+		assert_eq!(
+			map.get_distance_to_water(&HexCoord::from_offset_pos(12, 10), 4),
+			Some(2)
+		);
+		assert_eq!(map.get_distance_to_water(&HexCoord::from_offset_pos(30, 10), 4), None);
+		assert_eq!(map.get_distance_to_water(&HexCoord::from_offset_pos(30, 10), 0), None);
+	}
+
+	#[test]
+	fn tile_type()
+	{
+		let mut map = create_map();
+		let pos = HexCoord::from_offset_pos(70, 3);
+		let tile = TileAsset {
+			id: 3,
+			identifier: "paved".into(),
+			name: "Paved".into(),
+			texture_id: 5,
+			texture: String::new(),
+			texture_handle: Handle::default(),
+			side_texture_id: 6,
+			side_texture: String::new(),
+			side_texture_handle: Handle::default(),
+			move_cost: 0.5,
+		};
+		assert_eq!(map.get_tile_type(&pos), 0);
+		map.set_tile_type(&pos, &tile);
+		assert_eq!(map.get_tile_type(&pos), 3);
+		assert_eq!(map.chunks[1].textures[pos.to_chunk_local_index()], [5, 6]);
+		assert_eq!(map.get_tile_type(&HexCoord::from_offset_pos(6, 3)), 0);
+	}
+
+	#[test]
+	fn chunks_in_range()
+	{
+		let map = create_map();
+		let center = HexCoord::from_offset_pos(10, 10);
+		assert_eq!(map.get_chunks_in_range(&[center], 4), vec![0]);
+		let edge = HexCoord::from_offset_pos(62, 10);
+		assert_eq!(map.get_chunks_in_range(&[edge], 4), vec![0, 1]);
+		let corner = HexCoord::from_offset_pos(127, 63);
+		assert_eq!(map.get_chunks_in_range(&[corner, center], 4), vec![0, 1]);
+	}
+}
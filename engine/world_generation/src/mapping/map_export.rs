@@ -0,0 +1,320 @@
+use std::{
+	fmt::Display,
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::{Path, PathBuf},
+};
+
+use bevy::{
+	mesh::{Indices, VertexAttributeValues},
+	prelude::*,
+};
+use image::{GrayImage, ImageBuffer, Luma};
+use serde::{Deserialize, Serialize};
+
+use super::{
+	biome_map::BiomeMap,
+	map::Map,
+	map_renderer::{HillshadeRenderer, HypsometricRenderer, MapRenderer, render_with},
+};
+use crate::generators::mesh_generator::generate_chunk_mesh;
+
+/// Describes an exported map, written next to the exported files as `<name>.json`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MapExportMetadata
+{
+	pub name: String,
+	/// Size of the map in tiles
+	pub width: usize,
+	pub height: usize,
+	/// Size of the map in chunks
+	pub chunks_width: usize,
+	pub chunks_height: usize,
+	pub sealevel: f32,
+	/// Heights stored in the 16 bit heightmap are `min_level + value / 65535 * (max_level - min_level)`
+	pub min_level: f32,
+	pub max_level: f32,
+	pub biome_count: usize,
+	/// Exported files, relative to the metadata file
+	pub files: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum MapExportError
+{
+	Io(std::io::Error),
+	Image(image::ImageError),
+	Json(serde_json::Error),
+}
+
+impl Display for MapExportError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			MapExportError::Io(err) => write!(f, "Failed to write file: {}", err),
+			MapExportError::Image(err) => write!(f, "Failed to write image: {}", err),
+			MapExportError::Json(err) => write!(f, "Failed to write metadata: {}", err),
+		};
+	}
+}
+
+impl std::error::Error for MapExportError {}
+
+impl From<std::io::Error> for MapExportError
+{
+	fn from(value: std::io::Error) -> Self
+	{
+		return MapExportError::Io(value);
+	}
+}
+
+impl From<image::ImageError> for MapExportError
+{
+	fn from(value: image::ImageError) -> Self
+	{
+		return MapExportError::Image(value);
+	}
+}
+
+impl From<serde_json::Error> for MapExportError
+{
+	fn from(value: serde_json::Error) -> Self
+	{
+		return MapExportError::Json(value);
+	}
+}
+
+/// Exports every layer of the map into `dir`, prefixing all files with `name`.
+///
+/// Writes the heights as a 16 bit PNG and raw little endian f32, the terrain mesh as OBJ, the biome ids as an 8 bit
+/// index image, one weight image per biome, hypsometric and hillshade previews and a JSON metadata sidecar.
+pub fn export_map(
+	map: &Map,
+	biome_map: &BiomeMap,
+	dir: impl AsRef<Path>,
+	name: &str,
+) -> Result<MapExportMetadata, MapExportError>
+{
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Export Map").entered();
+	let dir = dir.as_ref();
+	fs::create_dir_all(dir)?;
+	let mut meta = MapExportMetadata {
+		name: name.to_string(),
+		width: map.get_tile_width(),
+		height: map.get_tile_height(),
+		chunks_width: map.width,
+		chunks_height: map.height,
+		sealevel: map.sealevel,
+		min_level: map.min_level,
+		max_level: map.max_level,
+		biome_count: biome_map.biome_count,
+		files: Vec::new(),
+	};
+	let mut file = |suffix: &str| -> PathBuf {
+		let file_name = format!("{}{}", name, suffix);
+		meta.files.push(file_name.clone());
+		return dir.join(file_name);
+	};
+
+	export_heights_png(map, file("_height.png"))?;
+	export_heights_raw(map, file("_height.f32"))?;
+	export_mesh_obj(map, file("_terrain.obj"))?;
+	export_biome_ids(biome_map, file("_biomes.png"))?;
+	for id in 0..biome_map.biome_count {
+		export_biome_weights(biome_map, id, file(&format!("_biome_{}.png", id)))?;
+	}
+	export_render(
+		&HypsometricRenderer::default(),
+		map,
+		biome_map,
+		file("_hypsometric.png"),
+	)?;
+	export_render(&HillshadeRenderer::default(), map, biome_map, file("_hillshade.png"))?;
+
+	let json = serde_json::to_string_pretty(&meta)?;
+	fs::write(dir.join(format!("{}.json", name)), json)?;
+	return Ok(meta);
+}
+
+/// Writes the heights as a 16 bit grayscale PNG, normalized between the map's min and max level
+pub fn export_heights_png(map: &Map, path: impl AsRef<Path>) -> Result<(), MapExportError>
+{
+	let heights = map.get_tile_heights();
+	let range = (map.max_level - map.min_level).max(f32::EPSILON);
+	let image: ImageBuffer<Luma<u16>, Vec<u16>> =
+		ImageBuffer::from_fn(map.get_tile_width() as u32, map.get_tile_height() as u32, |x, y| {
+			let h = heights[x as usize + y as usize * map.get_tile_width()];
+			let t = ((h - map.min_level) / range).clamp(0., 1.);
+			return Luma([(t * u16::MAX as f32).round() as u16]);
+		});
+	image.save(path)?;
+	return Ok(());
+}
+
+/// Writes the heights as row major little endian f32 values without a header
+pub fn export_heights_raw(map: &Map, path: impl AsRef<Path>) -> Result<(), MapExportError>
+{
+	let mut writer = BufWriter::new(File::create(path)?);
+	for h in map.get_tile_heights() {
+		writer.write_all(&h.to_le_bytes())?;
+	}
+	writer.flush()?;
+	return Ok(());
+}
+
+/// Writes the dominant biome of every tile as an 8 bit index image
+pub fn export_biome_ids(biome_map: &BiomeMap, path: impl AsRef<Path>) -> Result<(), MapExportError>
+{
+	let image = GrayImage::from_fn(biome_map.width as u32, biome_map.height as u32, |x, y| {
+		return Luma([biome_map.get_biome_id(x as usize, y as usize).min(u8::MAX as usize) as u8]);
+	});
+	image.save(path)?;
+	return Ok(());
+}
+
+/// Writes the blend weight of a single biome as an 8 bit grayscale image
+pub fn export_biome_weights(biome_map: &BiomeMap, biome: usize, path: impl AsRef<Path>) -> Result<(), MapExportError>
+{
+	let image = GrayImage::from_fn(biome_map.width as u32, biome_map.height as u32, |x, y| {
+		let weight = biome_map
+			.get_biome(x as i32, y as i32)
+			.and_then(|w| w.iter().find(|(id, _)| *id == biome))
+			.map_or(0., |(_, w)| w);
+		return Luma([(weight.clamp(0., 1.) * u8::MAX as f32).round() as u8]);
+	});
+	image.save(path)?;
+	return Ok(());
+}
+
+/// Renders the map with `renderer` and saves it as a PNG
+pub fn export_render(
+	renderer: &dyn MapRenderer,
+	map: &Map,
+	biome_map: &BiomeMap,
+	path: impl AsRef<Path>,
+) -> Result<(), MapExportError>
+{
+	render_with(renderer, map, biome_map).save(path)?;
+	return Ok(());
+}
+
+/// Writes the terrain meshes of every chunk into a single OBJ file in world space
+pub fn export_mesh_obj(map: &Map, path: impl AsRef<Path>) -> Result<(), MapExportError>
+{
+	let mut writer = BufWriter::new(File::create(path)?);
+	writeln!(writer, "o terrain")?;
+	let mut vertex_offset = 1;
+	for (index, chunk) in map.chunks.iter().enumerate() {
+		let mesh = generate_chunk_mesh(&map.get_chunk_mesh_data(index));
+		let offset = chunk.world_position();
+
+		let Some(positions) = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()) else {
+			continue;
+		};
+		for p in positions {
+			writeln!(writer, "v {} {} {}", p[0] + offset.x, p[1] + offset.y, p[2] + offset.z)?;
+		}
+		if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+			for uv in uvs {
+				writeln!(writer, "vt {} {}", uv[0], 1. - uv[1])?;
+			}
+		}
+		if let Some(normals) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()) {
+			for n in normals {
+				writeln!(writer, "vn {} {} {}", n[0], n[1], n[2])?;
+			}
+		}
+		if let Some(Indices::U32(indices)) = mesh.indices() {
+			//Bevy uses counter clockwise winding, same as OBJ
+			for tri in indices.chunks_exact(3) {
+				let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize + vertex_offset);
+				writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+			}
+		}
+		vertex_offset += positions.len();
+	}
+	writer.flush()?;
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests
+{
+	use hex::prelude::*;
+
+	use super::*;
+	use crate::mapping::{
+		biome_map::BiomeChunk,
+		map_import::{HeightmapImportConfig, import_heightmap},
+	};
+
+	fn create_maps() -> (Map, BiomeMap)
+	{
+		let mut chunk = Chunk::default();
+		for i in 0..Chunk::AREA {
+			chunk.heights[i] = (i % Chunk::SIZE) as f32 * 0.5;
+		}
+		let map = Map {
+			chunks: vec![chunk],
+			height: 1,
+			width: 1,
+			sealevel: 4.,
+			min_level: 0.,
+			max_level: 31.5,
+			biome_count: 2,
+			tile_classes: default(),
+		};
+		let mut biome_map = BiomeMap::new(UVec2::ONE, 2);
+		biome_map.chunks.push(BiomeChunk {
+			offset: UVec2::ZERO,
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		});
+		let ids: Vec<usize> = (0..Chunk::AREA).map(|i| (i / Chunk::SIZE >= 32) as usize).collect();
+		biome_map.set_biome_ids(&ids);
+		return (map, biome_map);
+	}
+
+	#[test]
+	fn export_layers()
+	{
+		let (map, biome_map) = create_maps();
+		let dir = std::env::temp_dir().join("phos_map_export_test");
+		let _ = fs::remove_dir_all(&dir);
+
+		fs::create_dir_all(&dir).unwrap();
+		export_heights_raw(&map, dir.join("height.f32")).unwrap();
+		let raw = fs::read(dir.join("height.f32")).unwrap();
+		let heights: Vec<f32> = raw
+			.chunks_exact(4)
+			.map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+			.collect();
+		assert_eq!(heights, map.get_tile_heights());
+
+		export_heights_png(&map, dir.join("height.png")).unwrap();
+		let cfg = HeightmapImportConfig {
+			scale: map.max_level - map.min_level,
+			offset: map.min_level,
+			sea_level: map.sealevel,
+			size: None,
+		};
+		let image = image::open(dir.join("height.png")).unwrap();
+		assert!(matches!(image, image::DynamicImage::ImageLuma16(_)));
+		export_biome_ids(&biome_map, dir.join("biomes.png")).unwrap();
+		let biomes = image::open(dir.join("biomes.png")).unwrap();
+		let (imported, imported_biomes) = import_heightmap(&image, Some(&biomes), &cfg).unwrap();
+		for (a, b) in imported.get_tile_heights().iter().zip(map.get_tile_heights()) {
+			assert!((a - b).abs() < 0.01, "Heights do not round trip {} != {}", a, b);
+		}
+		assert_eq!(imported_biomes.get_biome_ids(), biome_map.get_biome_ids());
+
+		export_biome_weights(&biome_map, 1, dir.join("biome_1.png")).unwrap();
+		let weights = image::open(dir.join("biome_1.png")).unwrap().to_luma8();
+		assert_eq!(weights.get_pixel(0, 0).0[0], 0);
+		assert_eq!(weights.get_pixel(0, 40).0[0], 255);
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+}
@@ -0,0 +1,265 @@
+use std::{fmt::Display, path::Path};
+
+use bevy::prelude::*;
+use hex::prelude::*;
+use image::{DynamicImage, ImageBuffer, Luma};
+
+use super::{
+	biome_map::{BiomeChunk, BiomeMap},
+	biome_regions::BiomeRegionGraph,
+	map::Map,
+};
+
+#[derive(Debug, Clone)]
+pub struct HeightmapImportConfig
+{
+	/// Height of a white pixel, black is always `0 * scale + offset`
+	pub scale: f32,
+	pub offset: f32,
+	pub sea_level: f32,
+	/// Size of the map in chunks, when not set the image is mapped one pixel per tile and rounded up to whole chunks
+	pub size: Option<UVec2>,
+}
+
+impl Default for HeightmapImportConfig
+{
+	fn default() -> Self
+	{
+		Self {
+			scale: 100.,
+			offset: 0.,
+			sea_level: 8.5,
+			size: None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum MapImportError
+{
+	Image(image::ImageError),
+	EmptyImage,
+	InvalidSize(UVec2),
+}
+
+impl Display for MapImportError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			MapImportError::Image(err) => write!(f, "Failed to read image: {}", err),
+			MapImportError::EmptyImage => write!(f, "Image has no pixels"),
+			MapImportError::InvalidSize(size) => write!(f, "Invalid map size: {}", size),
+		};
+	}
+}
+
+impl std::error::Error for MapImportError {}
+
+impl From<image::ImageError> for MapImportError
+{
+	fn from(value: image::ImageError) -> Self
+	{
+		return MapImportError::Image(value);
+	}
+}
+
+/// Loads a grayscale heightmap and an optional biome index image from disk, see [`import_heightmap`]
+pub fn import_heightmap_file(
+	path: impl AsRef<Path>,
+	biome_path: Option<&Path>,
+	cfg: &HeightmapImportConfig,
+) -> Result<(Map, BiomeMap), MapImportError>
+{
+	let heights = image::open(path)?;
+	let biomes = match biome_path {
+		Some(p) => Some(image::open(p)?),
+		None => None,
+	};
+	return import_heightmap(&heights, biomes.as_ref(), cfg);
+}
+
+/// Builds a map from an 8 or 16 bit grayscale image.
+///
+/// The image is stretched over the whole map and resampled at the center of every tile in offset coordinates, the
+/// same layout [`super::map_utils::render_map`] uses. The biome image is read from its gray or red channel as a biome
+/// index and sampled without filtering.
+pub fn import_heightmap(
+	heights: &DynamicImage,
+	biomes: Option<&DynamicImage>,
+	cfg: &HeightmapImportConfig,
+) -> Result<(Map, BiomeMap), MapImportError>
+{
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Import Heightmap").entered();
+	if heights.width() == 0 || heights.height() == 0 {
+		return Err(MapImportError::EmptyImage);
+	}
+	let size = cfg.size.unwrap_or(UVec2::new(
+		heights.width().div_ceil(Chunk::SIZE as u32),
+		heights.height().div_ceil(Chunk::SIZE as u32),
+	));
+	if size.x == 0 || size.y == 0 {
+		return Err(MapImportError::InvalidSize(size));
+	}
+
+	let gray = heights.to_luma16();
+	let biome_ids = biomes.map(|b| b.to_rgba8());
+	let tile_width = size.x as usize * Chunk::SIZE;
+	let tile_height = size.y as usize * Chunk::SIZE;
+	let tile_size = Vec2::new(tile_width as f32, tile_height as f32);
+
+	let mut chunks = Vec::with_capacity((size.x * size.y) as usize);
+	let mut ids = vec![0; tile_width * tile_height];
+	let mut biome_count = 1;
+	for cy in 0..size.y as usize {
+		for cx in 0..size.x as usize {
+			let mut chunk = Chunk {
+				chunk_offset: IVec2::new(cx as i32, cy as i32),
+				min_level: f32::MAX,
+				max_level: f32::MIN,
+				..default()
+			};
+			for z in 0..Chunk::SIZE {
+				for x in 0..Chunk::SIZE {
+					let tx = cx * Chunk::SIZE + x;
+					let tz = cy * Chunk::SIZE + z;
+					let uv = (Vec2::new(tx as f32, tz as f32) + 0.5) / tile_size;
+
+					let h = sample_bilinear(&gray, uv) * cfg.scale + cfg.offset;
+					let idx = x + z * Chunk::SIZE;
+					chunk.heights[idx] = h;
+					chunk.min_level = chunk.min_level.min(h);
+					chunk.max_level = chunk.max_level.max(h);
+
+					if let Some(biome_ids) = &biome_ids {
+						let px = (uv.x * biome_ids.width() as f32) as u32;
+						let py = (uv.y * biome_ids.height() as f32) as u32;
+						let id = biome_ids
+							.get_pixel(px.min(biome_ids.width() - 1), py.min(biome_ids.height() - 1))
+							.0[0] as usize;
+						chunk.biome_id[idx] = id;
+						ids[tx + tz * tile_width] = id;
+						biome_count = biome_count.max(id + 1);
+					}
+				}
+			}
+			chunks.push(chunk);
+		}
+	}
+
+	let mut biome_map = BiomeMap::new(size, biome_count);
+	biome_map.chunks = chunks
+		.iter()
+		.map(|c| BiomeChunk {
+			offset: c.chunk_offset.as_uvec2(),
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		})
+		.collect();
+	biome_map.set_biome_ids(&ids);
+	biome_map.regions = BiomeRegionGraph::build(&ids, tile_width, tile_height);
+
+	let mut map = Map {
+		min_level: chunks.iter().map(|c| c.min_level).fold(f32::MAX, f32::min),
+		max_level: chunks.iter().map(|c| c.max_level).fold(f32::MIN, f32::max),
+		chunks,
+		height: size.y as usize,
+		width: size.x as usize,
+		sealevel: cfg.sea_level,
+		biome_count,
+		tile_classes: default(),
+	};
+	map.build_tile_classes();
+	return Ok((map, biome_map));
+}
+
+/// Samples `image` at `uv` (0-1) with bilinear filtering, returning a value in 0-1
+fn sample_bilinear(image: &ImageBuffer<Luma<u16>, Vec<u16>>, uv: Vec2) -> f32
+{
+	let max = UVec2::new(image.width() - 1, image.height() - 1);
+	let p = (uv * Vec2::new(image.width() as f32, image.height() as f32) - 0.5).clamp(Vec2::ZERO, max.as_vec2());
+	let p0 = p.floor().as_uvec2();
+	let p1 = (p0 + 1).min(max);
+	let t = p - p0.as_vec2();
+
+	let get = |x: u32, y: u32| image.get_pixel(x, y).0[0] as f32 / u16::MAX as f32;
+	let top = get(p0.x, p0.y).lerp(get(p1.x, p0.y), t.x);
+	let bottom = get(p0.x, p1.y).lerp(get(p1.x, p1.y), t.x);
+	return top.lerp(bottom, t.y);
+}
+
+#[cfg(test)]
+mod tests
+{
+	use image::GrayImage;
+
+	use super::*;
+
+	#[test]
+	fn import_gradient()
+	{
+		//Left to right gradient, one pixel per tile
+		let img: ImageBuffer<Luma<u16>, Vec<u16>> =
+			ImageBuffer::from_fn(64, 64, |x, _| Luma([(x as f32 / 63. * u16::MAX as f32) as u16]));
+		let cfg = HeightmapImportConfig {
+			scale: 10.,
+			offset: 1.,
+			sea_level: 5.,
+			size: None,
+		};
+		let (map, biome_map) = import_heightmap(&DynamicImage::ImageLuma16(img), None, &cfg).unwrap();
+
+		assert_eq!(map.width, 1);
+		assert_eq!(map.height, 1);
+		assert_eq!(map.sealevel, 5.);
+		assert_eq!(map.biome_count, 1);
+		assert_eq!(biome_map.chunks.len(), 1);
+		assert!(map.min_level >= 1. && map.min_level < 1.5);
+		assert!(map.max_level <= 11. && map.max_level > 10.5);
+		assert_eq!(map.chunks[0].min_level, map.min_level);
+
+		let row: Vec<f32> = (0..Chunk::SIZE)
+			.map(|x| map.sample_height(&HexCoord::from_offset_pos(x, 10)))
+			.collect();
+		assert!(
+			row.windows(2).all(|w| w[0] <= w[1]),
+			"Heights should increase left to right"
+		);
+	}
+
+	#[test]
+	fn import_biomes_and_size()
+	{
+		let heights = GrayImage::from_pixel(32, 32, Luma([128]));
+		let biomes = GrayImage::from_fn(32, 32, |_, y| Luma([if y < 16 { 0 } else { 2 }]));
+		let cfg = HeightmapImportConfig {
+			size: Some(UVec2::new(2, 2)),
+			..default()
+		};
+		let (map, biome_map) = import_heightmap(
+			&DynamicImage::ImageLuma8(heights),
+			Some(&DynamicImage::ImageLuma8(biomes)),
+			&cfg,
+		)
+		.unwrap();
+
+		assert_eq!(map.chunks.len(), 4);
+		assert_eq!(map.chunks[3].chunk_offset, IVec2::new(1, 1));
+		assert_eq!(map.biome_count, 3);
+		assert_eq!(map.get_biome_id(&HexCoord::from_offset_pos(10, 10)), 0);
+		assert_eq!(map.get_biome_id(&HexCoord::from_offset_pos(100, 100)), 2);
+		assert_eq!(biome_map.get_biome_id(100, 100), 2);
+		assert!((map.sample_height(&HexCoord::from_offset_pos(70, 20)) - 50.2).abs() < 0.1);
+	}
+
+	#[test]
+	fn reject_empty_image()
+	{
+		let img = DynamicImage::ImageLuma8(GrayImage::new(0, 0));
+		assert!(matches!(
+			import_heightmap(&img, None, &default()),
+			Err(MapImportError::EmptyImage)
+		));
+	}
+}
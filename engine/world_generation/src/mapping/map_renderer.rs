@@ -0,0 +1,363 @@
+use bevy::{math::VectorSpace, prelude::*};
+use hex::prelude::*;
+use image::ImageBuffer;
+use rayon::prelude::*;
+
+use super::{biome_map::BiomeMap, map::Map};
+
+/// Draws a single pixel per tile of a [`Map`], used by the editor and when exporting map images
+pub trait MapRenderer: Sync
+{
+	fn get_color(&self, map: &Map, biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba;
+}
+
+/// Renders a whole map into a new image, one pixel per tile
+pub fn render_with(renderer: &dyn MapRenderer, map: &Map, biome_map: &BiomeMap)
+-> ImageBuffer<image::Rgba<u8>, Vec<u8>>
+{
+	let mut image = ImageBuffer::new(map.get_tile_width() as u32, map.get_tile_height() as u32);
+	update_with(renderer, map, biome_map, &mut image);
+	return image;
+}
+
+pub fn update_with(
+	renderer: &dyn MapRenderer,
+	map: &Map,
+	biome_map: &BiomeMap,
+	image: &mut ImageBuffer<image::Rgba<u8>, Vec<u8>>,
+)
+{
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Render Map Image").entered();
+	image.par_enumerate_pixels_mut().for_each(|(x, y, pixel)| {
+		let coord = HexCoord::from_offset_pos(x as usize, y as usize);
+		let color = renderer.get_color(map, biome_map, &coord);
+		*pixel = image::Rgba(Srgba::from(color).to_u8_array());
+	});
+}
+
+/// Gets the height gradient at `coord` in height units per world unit, missing neighbors are treated as flat
+pub fn get_height_gradient(map: &Map, coord: &HexCoord) -> Vec2
+{
+	let height = map.sample_height(coord);
+	let center = coord.to_world(0.).xz();
+	let mut gradient = Vec2::ZERO;
+	for (n, h) in coord.get_neighbors().iter().zip(map.get_neighbors(coord)) {
+		if let Some(h) = h {
+			gradient += (n.to_world(0.).xz() - center) * (h - height);
+		}
+	}
+	//The six neighbor offsets sum to 3r² along every axis
+	return gradient / (3. * SHORT_DIAGONAL * SHORT_DIAGONAL);
+}
+
+/// Colors sampled along a 0-1 range
+#[derive(Clone, Debug)]
+pub struct ColorRamp
+{
+	pub stops: Vec<(f32, LinearRgba)>,
+}
+
+impl ColorRamp
+{
+	pub fn new(stops: &[(f32, Srgba)]) -> Self
+	{
+		return ColorRamp {
+			stops: stops.iter().map(|(t, c)| (*t, LinearRgba::from(*c))).collect(),
+		};
+	}
+
+	pub fn sample(&self, t: f32) -> LinearRgba
+	{
+		let Some(first) = self.stops.first() else {
+			return LinearRgba::BLACK;
+		};
+		if t <= first.0 {
+			return first.1;
+		}
+		for pair in self.stops.windows(2) {
+			let (t0, c0) = pair[0];
+			let (t1, c1) = pair[1];
+			if t <= t1 {
+				let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1. };
+				return LinearRgba::lerp(c0, c1, f);
+			}
+		}
+		return self.stops.last().unwrap().1;
+	}
+}
+
+/// Colors land and water with separate ramps, split at sea level
+#[derive(Clone, Debug)]
+pub struct HypsometricRenderer
+{
+	pub land: ColorRamp,
+	pub water: ColorRamp,
+}
+
+impl Default for HypsometricRenderer
+{
+	fn default() -> Self
+	{
+		Self {
+			land: ColorRamp::new(&[
+				(0., Srgba::rgb(0.33, 0.55, 0.3)),
+				(0.3, Srgba::rgb(0.65, 0.7, 0.4)),
+				(0.6, Srgba::rgb(0.6, 0.45, 0.3)),
+				(0.85, Srgba::rgb(0.55, 0.5, 0.5)),
+				(1., Srgba::rgb(0.95, 0.95, 0.95)),
+			]),
+			water: ColorRamp::new(&[(0., Srgba::rgb(0.05, 0.1, 0.3)), (1., Srgba::rgb(0.3, 0.55, 0.75))]),
+		}
+	}
+}
+
+impl MapRenderer for HypsometricRenderer
+{
+	fn get_color(&self, map: &Map, _biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba
+	{
+		let height = map.sample_height(coord);
+		if height <= map.sealevel {
+			let t = height.remap(map.min_level, map.sealevel, 0., 1.);
+			return self.water.sample(if t.is_finite() { t } else { 1. });
+		}
+		let t = height.remap(map.sealevel, map.max_level, 0., 1.);
+		return self.land.sample(if t.is_finite() { t } else { 0. });
+	}
+}
+
+/// Grayscale relief lit by a directional light
+#[derive(Clone, Debug)]
+pub struct HillshadeRenderer
+{
+	/// Direction towards the light
+	pub light: Vec3,
+	/// Multiplier applied to heights before shading
+	pub exaggeration: f32,
+	/// Minimum brightness of surfaces facing away from the light
+	pub ambient: f32,
+}
+
+impl Default for HillshadeRenderer
+{
+	fn default() -> Self
+	{
+		Self {
+			light: Vec3::new(-1., 1.5, -1.).normalize(),
+			exaggeration: 1.,
+			ambient: 0.2,
+		}
+	}
+}
+
+impl MapRenderer for HillshadeRenderer
+{
+	fn get_color(&self, map: &Map, _biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba
+	{
+		let gradient = get_height_gradient(map, coord) * self.exaggeration;
+		let normal = Vec3::new(-gradient.x, 1., -gradient.y).normalize();
+		let shade = normal.dot(self.light).max(0.);
+		let v = self.ambient + (1. - self.ambient) * shade;
+		return LinearRgba::rgb(v, v, v);
+	}
+}
+
+/// Colors tiles by the steepness of the terrain
+#[derive(Clone, Debug)]
+pub struct SlopeRenderer
+{
+	/// Slope, in height units per world unit, at the end of the ramp
+	pub max_slope: f32,
+	pub ramp: ColorRamp,
+}
+
+impl Default for SlopeRenderer
+{
+	fn default() -> Self
+	{
+		Self {
+			max_slope: 2.,
+			ramp: ColorRamp::new(&[
+				(0., Srgba::rgb(0.2, 0.6, 0.2)),
+				(0.5, Srgba::rgb(0.9, 0.8, 0.2)),
+				(1., Srgba::rgb(0.8, 0.15, 0.1)),
+			]),
+		}
+	}
+}
+
+impl MapRenderer for SlopeRenderer
+{
+	fn get_color(&self, map: &Map, _biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba
+	{
+		let slope = get_height_gradient(map, coord).length();
+		return self.ramp.sample(slope / self.max_slope);
+	}
+}
+
+/// Mixes biome colors by their blend weights
+#[derive(Clone, Debug, Default)]
+pub struct BiomeRenderer
+{
+	/// Color of each biome, biomes without a color get an evenly spaced hue
+	pub colors: Vec<LinearRgba>,
+}
+
+impl MapRenderer for BiomeRenderer
+{
+	fn get_color(&self, _map: &Map, biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba
+	{
+		let off = coord.to_offset();
+		let Some(weights) = biome_map.get_biome(off.x, off.y) else {
+			return LinearRgba::BLACK;
+		};
+		let count = biome_map.biome_count.max(1) as f32;
+		let mut color = LinearRgba::BLACK;
+		for (id, w) in weights.iter() {
+			let c = self
+				.colors
+				.get(id)
+				.copied()
+				.unwrap_or_else(|| Hsla::hsl((id as f32 / count) * 360.0, 0.8, 0.7).into());
+			color = color + c * w;
+		}
+		color.alpha = 1.;
+		return color;
+	}
+}
+
+/// Draws contour lines every `interval` height units on top of another renderer
+#[derive(Clone, Debug)]
+pub struct ContourRenderer<R: MapRenderer = HypsometricRenderer>
+{
+	pub base: R,
+	pub interval: f32,
+	pub line_color: LinearRgba,
+}
+
+impl Default for ContourRenderer
+{
+	fn default() -> Self
+	{
+		Self {
+			base: default(),
+			interval: 5.,
+			line_color: LinearRgba::rgb(0.05, 0.05, 0.05),
+		}
+	}
+}
+
+impl<R: MapRenderer> MapRenderer for ContourRenderer<R>
+{
+	fn get_color(&self, map: &Map, biome_map: &BiomeMap, coord: &HexCoord) -> LinearRgba
+	{
+		let band = (map.sample_height(coord) / self.interval).floor();
+		//Only the higher side of a contour is drawn so lines stay one tile wide
+		let on_line = map
+			.get_neighbors(coord)
+			.iter()
+			.flatten()
+			.any(|h| (h / self.interval).floor() < band);
+		if on_line {
+			return self.line_color;
+		}
+		return self.base.get_color(map, biome_map, coord);
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mapping::biome_map::BiomeChunk;
+
+	fn create_maps(height: impl Fn(usize, usize) -> f32) -> (Map, BiomeMap)
+	{
+		let mut chunk = Chunk::default();
+		for y in 0..Chunk::SIZE {
+			for x in 0..Chunk::SIZE {
+				chunk.heights[x + y * Chunk::SIZE] = height(x, y);
+			}
+		}
+		let map = Map {
+			chunks: vec![chunk],
+			height: 1,
+			width: 1,
+			sealevel: 5.,
+			min_level: 0.,
+			max_level: 20.,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+		let mut biome_map = BiomeMap::new(UVec2::ONE, 1);
+		biome_map.chunks.push(BiomeChunk {
+			offset: UVec2::ZERO,
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		});
+		biome_map.set_biome_ids(&vec![0; Chunk::AREA]);
+		return (map, biome_map);
+	}
+
+	#[test]
+	fn color_ramp()
+	{
+		let ramp = ColorRamp {
+			stops: vec![(0., LinearRgba::BLACK), (1., LinearRgba::WHITE)],
+		};
+		assert_eq!(ramp.sample(-1.), LinearRgba::BLACK);
+		assert_eq!(ramp.sample(2.), LinearRgba::WHITE);
+		assert!((ramp.sample(0.25).red - 0.25).abs() < 0.001);
+	}
+
+	#[test]
+	fn gradient_of_ramp()
+	{
+		//Height increases by 1 per tile along x
+		let (map, _) = create_maps(|x, _| x as f32);
+		let g = get_height_gradient(&map, &HexCoord::from_offset_pos(20, 20));
+		assert!((g.x - 1. / SHORT_DIAGONAL).abs() < 0.01, "{}", g);
+		assert!(g.y.abs() < 0.01, "{}", g);
+
+		let (flat, _) = create_maps(|_, _| 3.);
+		assert_eq!(
+			get_height_gradient(&flat, &HexCoord::from_offset_pos(20, 20)),
+			Vec2::ZERO
+		);
+	}
+
+	#[test]
+	fn renderers()
+	{
+		let (map, biome_map) = create_maps(|x, _| if x < 32 { 0. } else { x as f32 - 30. });
+		let water = HexCoord::from_offset_pos(10, 10);
+		let land = HexCoord::from_offset_pos(50, 10);
+
+		let hypsometric = HypsometricRenderer::default();
+		let w = hypsometric.get_color(&map, &biome_map, &water);
+		let l = hypsometric.get_color(&map, &biome_map, &HexCoord::from_offset_pos(40, 10));
+		assert!(w.blue > w.green && l.green > l.blue);
+
+		let slope = SlopeRenderer::default();
+		assert_eq!(slope.get_color(&map, &biome_map, &water), slope.ramp.sample(0.));
+
+		let hillshade = HillshadeRenderer::default();
+		let flat = hillshade.get_color(&map, &biome_map, &water);
+		assert!((flat.red - (0.2 + 0.8 * hillshade.light.y)).abs() < 0.001);
+		//Slopes rising towards +x face the light and are brighter than flat ground
+		assert!(hillshade.get_color(&map, &biome_map, &land).red > flat.red);
+
+		let contour = ContourRenderer::default();
+		let lines = (0..Chunk::SIZE)
+			.filter(|x| contour.get_color(&map, &biome_map, &HexCoord::from_offset_pos(*x, 10)) == contour.line_color)
+			.count();
+		assert!(lines > 0);
+		assert_eq!(
+			contour.get_color(&map, &biome_map, &water),
+			hypsometric.get_color(&map, &biome_map, &water)
+		);
+
+		let image = render_with(&BiomeRenderer::default(), &map, &biome_map);
+		assert_eq!(image.dimensions(), (64, 64));
+	}
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{biome_map::BiomeMap, biome_regions::get_tile_neighbors, map::Map, regions::label_components};
+
+/// Number of buckets in the height and slope histograms
+const HISTOGRAM_BUCKETS: usize = 32;
+
+/// Summary of a generated map, used to compare generation presets and seeds
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MapStats
+{
+	pub tile_count: usize,
+	pub sealevel: f32,
+	pub min_height: f32,
+	pub max_height: f32,
+	pub mean_height: f32,
+	pub height_histogram: Histogram,
+	pub land_tiles: usize,
+	pub water_tiles: usize,
+	/// Fraction of tiles above sea level
+	pub land_ratio: f32,
+	/// Area covered by each biome, blend weights are counted fractionally
+	pub biomes: Vec<BiomeStats>,
+	/// Slope of every tile, see [`Map::slope_at`]
+	pub slope_histogram: Histogram,
+	pub mean_slope: f32,
+	/// Number of tile edges between land and water
+	pub coastline_length: usize,
+	pub landmass_count: usize,
+	/// Area of the largest landmass in tiles
+	pub largest_landmass: usize,
+	/// Tiles whose [`TileClass`](super::tile_class::TileClass) is buildable
+	pub buildable_tiles: usize,
+	/// Fraction of land tiles that are buildable
+	pub buildable_ratio: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Histogram
+{
+	pub min: f32,
+	pub bucket_size: f32,
+	pub counts: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BiomeStats
+{
+	pub id: usize,
+	pub area: f32,
+	/// Percentage of the map covered by this biome
+	pub percent: f32,
+}
+
+impl MapStats
+{
+	pub fn compute(map: &Map, biome_map: &BiomeMap) -> Self
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Compute Map Stats").entered();
+		let width = map.get_tile_width();
+		let height = map.get_tile_height();
+		let heights = map.get_tile_heights();
+		let count = heights.len();
+		let classes: Vec<_> = (0..count)
+			.map(|i| map.get_cached_tile_class(&HexCoord::from_offset_pos(i % width, i / width)))
+			.collect();
+		let is_land = |i: usize| !classes[i].is_water();
+
+		let mut stats = MapStats {
+			tile_count: count,
+			sealevel: map.sealevel,
+			min_height: heights.iter().copied().fold(f32::MAX, f32::min),
+			max_height: heights.iter().copied().fold(f32::MIN, f32::max),
+			mean_height: heights.iter().sum::<f32>() / count.max(1) as f32,
+			..default()
+		};
+		stats.height_histogram = Histogram::new(&heights, stats.min_height, stats.max_height, HISTOGRAM_BUCKETS);
+
+		let slopes: Vec<f32> = (0..count)
+			.map(|i| map.slope_at(&HexCoord::from_offset_pos(i % width, i / width)))
+			.collect();
+		for tile in 0..count {
+			if !is_land(tile) {
+				continue;
+			}
+			stats.land_tiles += 1;
+			stats.coastline_length += get_tile_neighbors(tile, width, height).filter(|n| !is_land(*n)).count();
+			if classes[tile].is_buildable() {
+				stats.buildable_tiles += 1;
+			}
+		}
+		stats.water_tiles = count - stats.land_tiles;
+		stats.land_ratio = stats.land_tiles as f32 / count.max(1) as f32;
+		stats.buildable_ratio = stats.buildable_tiles as f32 / stats.land_tiles.max(1) as f32;
+		stats.mean_slope = slopes.iter().sum::<f32>() / count.max(1) as f32;
+		let max_slope = slopes.iter().copied().fold(0., f32::max);
+		stats.slope_histogram = Histogram::new(&slopes, 0., max_slope, HISTOGRAM_BUCKETS);
+
+		let land: Vec<Option<usize>> = (0..count).map(|i| is_land(i).then_some(0)).collect();
+		let (_, landmasses) = label_components(&land, width, height);
+		stats.landmass_count = landmasses.len();
+		stats.largest_landmass = landmasses.iter().map(|c| c.area).max().unwrap_or(0);
+
+		let mut areas = vec![0.; biome_map.biome_count];
+		for y in 0..height {
+			for x in 0..width {
+				let Some(weights) = biome_map.get_biome(x as i32, y as i32) else {
+					continue;
+				};
+				for (id, w) in weights.iter() {
+					if id >= areas.len() {
+						areas.resize(id + 1, 0.);
+					}
+					areas[id] += w;
+				}
+			}
+		}
+		stats.biomes = areas
+			.into_iter()
+			.enumerate()
+			.map(|(id, area)| BiomeStats {
+				id,
+				area,
+				percent: area / count.max(1) as f32 * 100.,
+			})
+			.collect();
+
+		return stats;
+	}
+
+	pub fn to_json(&self) -> serde_json::Result<String>
+	{
+		return serde_json::to_string_pretty(self);
+	}
+}
+
+impl Histogram
+{
+	/// Sorts `values` into `buckets` evenly sized buckets between `min` and `max`
+	pub fn new(values: &[f32], min: f32, max: f32, buckets: usize) -> Self
+	{
+		let range = (max - min).max(f32::EPSILON);
+		let bucket_size = range / buckets as f32;
+		let mut counts = vec![0; buckets];
+		for v in values {
+			let i = ((v - min) / bucket_size) as usize;
+			counts[i.min(buckets - 1)] += 1;
+		}
+		return Histogram {
+			min,
+			bucket_size,
+			counts,
+		};
+	}
+
+	/// Gets the lower bound of the bucket at `index`
+	pub fn get_bucket_start(&self, index: usize) -> f32
+	{
+		return self.min + self.bucket_size * index as f32;
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mapping::biome_map::BiomeChunk;
+
+	#[test]
+	fn island_stats()
+	{
+		let mut chunk = Chunk::default();
+		for y in 0..Chunk::SIZE {
+			for x in 0..Chunk::SIZE {
+				let land = (16..48).contains(&x) && (16..48).contains(&y);
+				chunk.heights[x + y * Chunk::SIZE] = if land { 10. } else { 0. };
+			}
+		}
+		//A hill too steep to build on, together with its neighbors
+		chunk.heights[30 + 30 * Chunk::SIZE] = 15.;
+		let map = Map {
+			chunks: vec![chunk],
+			height: 1,
+			width: 1,
+			sealevel: 5.,
+			min_level: 0.,
+			max_level: 20.,
+			biome_count: 2,
+			tile_classes: default(),
+		};
+		let mut biome_map = BiomeMap::new(UVec2::ONE, 2);
+		biome_map.chunks.push(BiomeChunk {
+			offset: UVec2::ZERO,
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		});
+		let ids: Vec<usize> = (0..Chunk::AREA).map(|i| (i % Chunk::SIZE >= 48) as usize).collect();
+		biome_map.set_biome_ids(&ids);
+
+		let stats = MapStats::compute(&map, &biome_map);
+		assert_eq!(stats.tile_count, Chunk::AREA);
+		assert_eq!(stats.land_tiles, 32 * 32);
+		assert_eq!(stats.water_tiles, Chunk::AREA - 32 * 32);
+		assert_eq!(stats.landmass_count, 1);
+		assert_eq!(stats.largest_landmass, 32 * 32);
+		assert_eq!(stats.height_histogram.counts.iter().sum::<usize>(), Chunk::AREA);
+		assert_eq!(stats.height_histogram.counts[0], Chunk::AREA - 32 * 32);
+		assert!(stats.coastline_length >= 4 * 32);
+		assert_eq!(stats.buildable_tiles, 32 * 32 - 7);
+		assert_eq!(stats.slope_histogram.counts.iter().sum::<usize>(), Chunk::AREA);
+		assert_eq!(stats.biomes.len(), 2);
+		assert!((stats.biomes[1].percent - 25.).abs() < 0.01);
+
+		let json = stats.to_json().unwrap();
+		let parsed: MapStats = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, stats);
+	}
+}
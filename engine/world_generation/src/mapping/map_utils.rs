@@ -29,20 +29,30 @@ pub fn update_image(
 	image: &mut ImageBuffer<image::Rgba<u8>, Vec<u8>>,
 )
 {
-	let min = *data.iter().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(&0.0);
-	let max = *data.iter().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(&1.0);
+	let (min, max) = get_min_max(data);
 
 	let w = size.x * Chunk::SIZE as u32;
 
 	image.par_enumerate_pixels_mut().for_each(|(x, y, pixel)| {
 		let idx = (y * w + x) as usize;
 		let v = data[idx];
-		let t = v.remap(min, max, 0.0, 1.0);
+		let t = if max > min { v.remap(min, max, 0.0, 1.0) } else { 0.0 };
 		let col = LinearRgba::lerp(color1, color2, t);
 		*pixel = to_pixel(&col);
 	});
 }
 
+/// Gets the lowest and highest value in `data`, ignoring NaN. Returns `(0, 1)` when there are no values
+fn get_min_max(data: &[f32]) -> (f32, f32)
+{
+	let values = data.iter().copied().filter(|v| !v.is_nan());
+	let (min, max) = values.fold((f32::MAX, f32::MIN), |(min, max), v| (min.min(v), max.max(v)));
+	if min > max {
+		return (0.0, 1.0);
+	}
+	return (min, max);
+}
+
 fn to_pixel(col: &LinearRgba) -> image::Rgba<u8>
 {
 	return image::Rgba([
@@ -69,7 +79,7 @@ pub fn update_map(map: &Map, smooth: f32, image: &mut ImageBuffer<image::Rgba<u8
 		let height = map.sample_height(&coord);
 
 		let mut color = Hsla::hsl(138.0, 1.0, 0.4);
-		if height < map.sealevel {
+		if map.is_underwater(&coord) {
 			color.hue = 217.0;
 		}
 
@@ -147,9 +157,9 @@ pub fn update_biome_map(map: &Map, biome_map: &BiomeMap, image: &mut ImageBuffer
 		let biome_blend = biome_map.get_biome(x as i32, y as i32).unwrap();
 		let right = coord.get_neighbor(1);
 		let mut color = Oklaba::BLACK;
-		for (i, blend) in biome_blend.iter().enumerate() {
+		for (i, blend) in biome_blend.iter() {
 			let mut c: Oklaba = Hsla::hsl((i as f32 / map_biome_count) * 360.0, 0.8, 0.7).into();
-			c *= *blend;
+			c *= blend;
 			color = Oklaba::add(c, color);
 		}
 		if map.is_in_bounds(&right) {
@@ -161,3 +171,25 @@ pub fn update_biome_map(map: &Map, biome_map: &BiomeMap, image: &mut ImageBuffer
 		*pixel = to_pixel(&color.into());
 	});
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn min_max()
+	{
+		assert_eq!(get_min_max(&[3., -2., f32::NAN, 7.5]), (-2., 7.5));
+		assert_eq!(get_min_max(&[]), (0., 1.));
+	}
+
+	#[test]
+	fn image_normalization()
+	{
+		let data: Vec<f32> = (0..Chunk::AREA).map(|i| (i % Chunk::SIZE) as f32 + 10.).collect();
+		let image = render_image(UVec2::ONE, &data, LinearRgba::BLACK, LinearRgba::WHITE);
+		assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+		assert_eq!(image.get_pixel(63, 0).0, [255, 255, 255, 255]);
+	}
+}
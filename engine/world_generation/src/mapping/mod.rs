@@ -1,5 +1,13 @@
 pub mod biome_map;
+pub mod biome_regions;
 pub mod config;
 pub mod map;
+pub mod map_export;
+pub mod map_import;
+pub mod map_renderer;
+pub mod map_stats;
 pub mod map_utils;
 pub mod mesh_chunk;
+pub mod regions;
+pub mod slope_map;
+pub mod tile_class;
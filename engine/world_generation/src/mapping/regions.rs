@@ -0,0 +1,595 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+
+use super::{
+	biome_map::BiomeMap,
+	biome_regions::{BiomeRegionGraph, get_tile_neighbors},
+	map::Map,
+};
+use crate::biome_painter::BiomePainter;
+
+/// Height above sea level, as a fraction of the highest peak, at which land counts as mountains
+const MOUNTAIN_HEIGHT: f32 = 0.6;
+/// Mountain areas smaller than this are treated as lone peaks and not named
+const MIN_MOUNTAIN_AREA: usize = 24;
+/// Bodies of water up to this size are lakes rather than seas
+const MAX_LAKE_AREA: usize = 256;
+/// Seas at least this size are named oceans
+const MIN_OCEAN_AREA: usize = 16384;
+/// Landmasses smaller than this are named islands
+const MIN_CONTINENT_AREA: usize = 2048;
+
+const SYLLABLES: [&str; 32] = [
+	"al", "an", "ar", "ash", "bel", "bran", "cor", "dra", "el", "en", "fal", "gar", "hal", "is", "kar", "kel", "lor",
+	"mar", "mor", "nor", "or", "ran", "sel", "sha", "tar", "thal", "ul", "val", "ven", "vor", "wen", "zar",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RegionKind
+{
+	Landmass,
+	Sea,
+	Lake,
+	MountainRange,
+	Biome,
+}
+
+impl RegionKind
+{
+	pub fn is_water(&self) -> bool
+	{
+		return matches!(self, RegionKind::Sea | RegionKind::Lake);
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Region
+{
+	pub id: usize,
+	pub kind: RegionKind,
+	pub name: String,
+	/// Number of tiles in the region
+	pub area: usize,
+	/// Bounding box in offset tile coordinates, inclusive
+	pub bounds: URect,
+	/// Biome of the region, only set for [`RegionKind::Biome`]
+	pub biome: Option<usize>,
+	/// Landmass or body of water that holds most of this region, not set for landmasses and seas
+	pub parent: Option<usize>,
+	/// Regions of the same layer that share a border with this one. Landmasses, seas and lakes are one layer
+	pub neighbors: Vec<usize>,
+}
+
+/// Named landmasses, seas, mountain ranges and biome regions of the current map
+#[derive(Resource, Clone, Default)]
+pub struct WorldRegions
+{
+	pub regions: Vec<Region>,
+	pub width: usize,
+	pub height: usize,
+	/// Landmass, sea or lake of every tile, row major
+	terrain: Vec<usize>,
+	/// Mountain range of every tile, row major
+	mountains: Vec<Option<usize>>,
+	/// Biome region of every tile, row major
+	biomes: Vec<usize>,
+}
+
+/// A connected group of tiles found by [`label_components`]
+pub(crate) struct ConnectedArea
+{
+	pub(crate) class: usize,
+	pub(crate) area: usize,
+	pub(crate) min: UVec2,
+	pub(crate) max: UVec2,
+	pub(crate) sum: Vec2,
+}
+
+impl WorldRegions
+{
+	pub fn generate(map: &Map, biome_map: &BiomeMap, painter: &BiomePainter, seed: u32) -> Self
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Generate World Regions").entered();
+		let width = map.get_tile_width();
+		let height = map.get_tile_height();
+		let heights = map.get_tile_heights();
+
+		let mut result = WorldRegions {
+			regions: Vec::new(),
+			width,
+			height,
+			terrain: vec![0; heights.len()],
+			mountains: vec![None; heights.len()],
+			biomes: vec![0; heights.len()],
+		};
+		let mut names = NameGenerator::new(seed, width, height);
+
+		//Landmasses, seas and lakes
+		let land: Vec<Option<usize>> = heights.iter().map(|h| Some((*h > map.sealevel) as usize)).collect();
+		let (labels, components) = label_components(&land, width, height);
+		let terrain_start = result.regions.len();
+		for c in &components {
+			let kind = match c.class {
+				1 => RegionKind::Landmass,
+				_ if c.area <= MAX_LAKE_AREA => RegionKind::Lake,
+				_ => RegionKind::Sea,
+			};
+			let name = names.terrain_name(kind, c.area);
+			result.push_region(kind, name, c, None, None);
+		}
+		for (tile, label) in labels.iter().enumerate() {
+			result.terrain[tile] = terrain_start + label.unwrap();
+		}
+		result.link_neighbors(&labels, terrain_start, width, height);
+
+		//Mountain ranges
+		let peak = heights.iter().copied().fold(map.sealevel, f32::max);
+		let mountain_height = map.sealevel + (peak - map.sealevel) * MOUNTAIN_HEIGHT;
+		let mountain: Vec<Option<usize>> = heights
+			.iter()
+			.map(|h| {
+				if peak > map.sealevel && *h >= mountain_height {
+					Some(0)
+				} else {
+					None
+				}
+			})
+			.collect();
+		let (labels, components) = label_components(&mountain, width, height);
+		let mut mountain_ids = vec![None; components.len()];
+		for (i, c) in components.iter().enumerate() {
+			if c.area < MIN_MOUNTAIN_AREA {
+				continue;
+			}
+			mountain_ids[i] = Some(result.regions.len());
+			let name = names.mountain_name(c);
+			result.push_region(RegionKind::MountainRange, name, c, None, None);
+		}
+		for (tile, label) in labels.iter().enumerate() {
+			result.mountains[tile] = label.and_then(|l| mountain_ids[l]);
+		}
+
+		//Biome regions
+		let built;
+		let graph = if biome_map.regions.tile_regions.len() == heights.len() {
+			&biome_map.regions
+		} else {
+			built = BiomeRegionGraph::build(&biome_map.get_biome_ids(), width, height);
+			&built
+		};
+		let mut components: Vec<ConnectedArea> = graph
+			.regions
+			.iter()
+			.map(|r| ConnectedArea {
+				class: r.biome,
+				area: 0,
+				min: UVec2::MAX,
+				max: UVec2::ZERO,
+				sum: Vec2::ZERO,
+			})
+			.collect();
+		for (tile, r) in graph.tile_regions.iter().enumerate() {
+			components[*r].add(tile, width);
+		}
+		let biome_start = result.regions.len();
+		for c in &components {
+			let biome_name = painter.biomes.get(c.class).map_or("Wilds", |b| b.name.as_str());
+			let name = names.biome_name(c, biome_name);
+			result.push_region(RegionKind::Biome, name, c, Some(c.class), None);
+		}
+		for (tile, r) in graph.tile_regions.iter().enumerate() {
+			result.biomes[tile] = biome_start + r;
+		}
+		for region in &graph.regions {
+			result.regions[biome_start + region.id].neighbors =
+				region.neighbors.iter().map(|e| biome_start + e.region).collect();
+		}
+
+		result.assign_parents();
+		return result;
+	}
+
+	fn push_region(
+		&mut self,
+		kind: RegionKind,
+		name: String,
+		c: &ConnectedArea,
+		biome: Option<usize>,
+		parent: Option<usize>,
+	)
+	{
+		self.regions.push(Region {
+			id: self.regions.len(),
+			kind,
+			name,
+			area: c.area,
+			bounds: URect::from_corners(c.min, c.max),
+			biome,
+			parent,
+			neighbors: Vec::new(),
+		});
+	}
+
+	fn link_neighbors(&mut self, labels: &[Option<usize>], start: usize, width: usize, height: usize)
+	{
+		for (tile, label) in labels.iter().enumerate() {
+			let Some(a) = label else {
+				continue;
+			};
+			for n in get_tile_neighbors(tile, width, height) {
+				if let Some(b) = labels[n]
+					&& b != *a && !self.regions[start + a].neighbors.contains(&(start + b))
+				{
+					self.regions[start + a].neighbors.push(start + b);
+				}
+			}
+		}
+		for region in self.regions[start..].iter_mut() {
+			region.neighbors.sort();
+		}
+	}
+
+	/// Links mountain ranges and biome regions to the landmass or body of water holding most of their tiles
+	fn assign_parents(&mut self)
+	{
+		let mut counts: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.regions.len()];
+		for tile in 0..self.terrain.len() {
+			let t = self.terrain[tile];
+			let children = [self.mountains[tile], Some(self.biomes[tile])];
+			for child in children.into_iter().flatten() {
+				match counts[child].iter_mut().find(|(r, _)| *r == t) {
+					Some((_, c)) => *c += 1,
+					None => counts[child].push((t, 1)),
+				}
+			}
+		}
+		for (region, counts) in self.regions.iter_mut().zip(counts) {
+			region.parent = counts.iter().max_by_key(|(r, c)| (*c, usize::MAX - r)).map(|(r, _)| *r);
+		}
+	}
+
+	pub fn get_region(&self, id: usize) -> Option<&Region>
+	{
+		return self.regions.get(id);
+	}
+
+	/// Gets the most specific named region at `pos`.
+	///
+	/// This is the mountain range on mountains, the biome region on other land and the sea or lake on water.
+	pub fn region_at(&self, pos: &HexCoord) -> Option<&Region>
+	{
+		if let Some(mountain) = self.mountain_at(pos) {
+			return Some(mountain);
+		}
+		let terrain = self.terrain_at(pos)?;
+		if terrain.kind.is_water() {
+			return Some(terrain);
+		}
+		return self.biome_region_at(pos);
+	}
+
+	/// Gets the landmass, sea or lake at `pos`
+	pub fn terrain_at(&self, pos: &HexCoord) -> Option<&Region>
+	{
+		let tile = self.get_tile_index(pos)?;
+		return self.regions.get(self.terrain[tile]);
+	}
+
+	pub fn mountain_at(&self, pos: &HexCoord) -> Option<&Region>
+	{
+		let tile = self.get_tile_index(pos)?;
+		return self.mountains[tile].map(|r| &self.regions[r]);
+	}
+
+	pub fn biome_region_at(&self, pos: &HexCoord) -> Option<&Region>
+	{
+		let tile = self.get_tile_index(pos)?;
+		return self.regions.get(self.biomes[tile]);
+	}
+
+	/// Gets the landmass `pos` is on, [`None`] when it is on water
+	pub fn landmass_at(&self, pos: &HexCoord) -> Option<&Region>
+	{
+		return self.terrain_at(pos).filter(|r| r.kind == RegionKind::Landmass);
+	}
+
+	/// Checks if a naval unit can travel between `a` and `b` without crossing land
+	pub fn is_water_connected(&self, a: &HexCoord, b: &HexCoord) -> bool
+	{
+		return match (self.terrain_at(a), self.terrain_at(b)) {
+			(Some(ra), Some(rb)) => ra.kind.is_water() && ra.id == rb.id,
+			_ => false,
+		};
+	}
+
+	pub fn iter_kind(&self, kind: RegionKind) -> impl Iterator<Item = &Region>
+	{
+		return self.regions.iter().filter(move |r| r.kind == kind);
+	}
+
+	fn get_tile_index(&self, pos: &HexCoord) -> Option<usize>
+	{
+		if self.terrain.is_empty() || !pos.is_in_bounds(self.height, self.width) {
+			return None;
+		}
+		return Some(pos.to_index(self.width));
+	}
+}
+
+impl ConnectedArea
+{
+	fn add(&mut self, tile: usize, width: usize)
+	{
+		let p = UVec2::new((tile % width) as u32, (tile / width) as u32);
+		self.area += 1;
+		self.min = self.min.min(p);
+		self.max = self.max.max(p);
+		self.sum += p.as_vec2();
+	}
+
+	fn center(&self) -> Vec2
+	{
+		return self.sum / self.area as f32;
+	}
+}
+
+/// Flood fills tiles of the same class into connected components, tiles without a class are skipped
+pub(crate) fn label_components(
+	classes: &[Option<usize>],
+	width: usize,
+	height: usize,
+) -> (Vec<Option<usize>>, Vec<ConnectedArea>)
+{
+	let mut labels = vec![None; classes.len()];
+	let mut components = Vec::new();
+	let mut open = Vec::new();
+	for start in 0..classes.len() {
+		let Some(class) = classes[start] else {
+			continue;
+		};
+		if labels[start].is_some() {
+			continue;
+		}
+		let id = components.len();
+		let mut c = ConnectedArea {
+			class,
+			area: 0,
+			min: UVec2::MAX,
+			max: UVec2::ZERO,
+			sum: Vec2::ZERO,
+		};
+		labels[start] = Some(id);
+		open.push(start);
+		while let Some(tile) = open.pop() {
+			c.add(tile, width);
+			for n in get_tile_neighbors(tile, width, height) {
+				if labels[n].is_none() && classes[n] == Some(class) {
+					labels[n] = Some(id);
+					open.push(n);
+				}
+			}
+		}
+		components.push(c);
+	}
+	return (labels, components);
+}
+
+/// Generates deterministic region names from the map seed
+struct NameGenerator
+{
+	state: u64,
+	size: Vec2,
+	used: Vec<String>,
+}
+
+impl NameGenerator
+{
+	fn new(seed: u32, width: usize, height: usize) -> Self
+	{
+		return NameGenerator {
+			state: seed as u64 ^ 0x9E37_79B9_7F4A_7C15,
+			size: Vec2::new(width as f32, height as f32),
+			used: Vec::new(),
+		};
+	}
+
+	fn next(&mut self) -> u64
+	{
+		//splitmix64
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		return z ^ (z >> 31);
+	}
+
+	fn word(&mut self) -> String
+	{
+		let count = 2 + (self.next() % 2) as usize;
+		let mut word = String::new();
+		for _ in 0..count {
+			word.push_str(SYLLABLES[(self.next() % SYLLABLES.len() as u64) as usize]);
+		}
+		let mut chars = word.chars();
+		let first = chars.next().unwrap().to_ascii_uppercase();
+		return std::iter::once(first).chain(chars).collect();
+	}
+
+	/// Gets the compass direction of a point relative to the map center, [`None`] near the center
+	fn direction(&self, center: Vec2) -> Option<&'static str>
+	{
+		let rel = center / self.size;
+		let v = if rel.y < 1. / 3. {
+			"North"
+		} else if rel.y > 2. / 3. {
+			"South"
+		} else {
+			""
+		};
+		let h = if rel.x < 1. / 3. {
+			"west"
+		} else if rel.x > 2. / 3. {
+			"east"
+		} else {
+			""
+		};
+		return match (v, h) {
+			("", "") => None,
+			("", "west") => Some("Western"),
+			("", "east") => Some("Eastern"),
+			("North", "") => Some("Northern"),
+			("South", "") => Some("Southern"),
+			("North", "west") => Some("Northwestern"),
+			("North", "east") => Some("Northeastern"),
+			("South", "west") => Some("Southwestern"),
+			_ => Some("Southeastern"),
+		};
+	}
+
+	/// Picks the first unused candidate, falling back to generated words
+	fn unique(&mut self, candidate: String, fallback: impl Fn(&str) -> String) -> String
+	{
+		let mut name = candidate;
+		while self.used.contains(&name) {
+			let word = self.word();
+			name = fallback(&word);
+		}
+		self.used.push(name.clone());
+		return name;
+	}
+
+	fn terrain_name(&mut self, kind: RegionKind, area: usize) -> String
+	{
+		let word = self.word();
+		let name = move |w: &str| match kind {
+			RegionKind::Lake => format!("Lake {}", w),
+			RegionKind::Sea if area >= MIN_OCEAN_AREA => format!("{} Ocean", w),
+			RegionKind::Sea => format!("{} Sea", w),
+			_ if area < MIN_CONTINENT_AREA => format!("Isle of {}", w),
+			_ => w.to_string(),
+		};
+		return self.unique(name(&word), name);
+	}
+
+	fn mountain_name(&mut self, c: &ConnectedArea) -> String
+	{
+		let word = self.word();
+		let candidate = match self.direction(c.center()) {
+			Some(dir) => format!("{} {} Mountains", dir, word),
+			None => format!("{} Mountains", word),
+		};
+		return self.unique(candidate, |w| format!("{} Mountains", w));
+	}
+
+	fn biome_name(&mut self, c: &ConnectedArea, biome: &str) -> String
+	{
+		let candidate = match self.direction(c.center()) {
+			Some(dir) => format!("{} {}", dir, biome),
+			None => format!("Central {}", biome),
+		};
+		return self.unique(candidate, |w| format!("{} of {}", biome, w));
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mapping::biome_map::BiomeChunk;
+
+	fn create_map(height: impl Fn(usize, usize) -> f32) -> Map
+	{
+		let mut chunk = Chunk::default();
+		for y in 0..Chunk::SIZE {
+			for x in 0..Chunk::SIZE {
+				chunk.heights[x + y * Chunk::SIZE] = height(x, y);
+			}
+		}
+		return Map {
+			chunks: vec![chunk],
+			height: 1,
+			width: 1,
+			sealevel: 5.,
+			min_level: 0.,
+			max_level: 20.,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+	}
+
+	fn generate(map: &Map) -> WorldRegions
+	{
+		let mut biome_map = BiomeMap::new(UVec2::ONE, 1);
+		biome_map.chunks.push(BiomeChunk {
+			offset: UVec2::ZERO,
+			data: [default(); Chunk::AREA],
+			tiles: vec![default(); Chunk::AREA],
+		});
+		biome_map.set_biome_ids(&vec![0; Chunk::AREA]);
+		let painter = BiomePainter::new(Vec::new());
+		return WorldRegions::generate(map, &biome_map, &painter, 7);
+	}
+
+	#[test]
+	fn land_and_water_regions()
+	{
+		//Two islands with a mountain on the first, surrounded by sea
+		let map = create_map(|x, y| {
+			if (8..24).contains(&x) && (8..24).contains(&y) {
+				return if (12..20).contains(&x) && (12..20).contains(&y) {
+					20.
+				} else {
+					10.
+				};
+			}
+			if (40..56).contains(&x) && (40..56).contains(&y) {
+				return 10.;
+			}
+			return 0.;
+		});
+		let regions = generate(&map);
+
+		assert_eq!(regions.iter_kind(RegionKind::Landmass).count(), 2);
+		assert_eq!(regions.iter_kind(RegionKind::Sea).count(), 1);
+		assert_eq!(regions.iter_kind(RegionKind::MountainRange).count(), 1);
+
+		let peak = regions.region_at(&HexCoord::from_offset_pos(15, 15)).unwrap();
+		assert_eq!(peak.kind, RegionKind::MountainRange);
+		let island = regions.landmass_at(&HexCoord::from_offset_pos(15, 15)).unwrap();
+		assert_eq!(peak.parent, Some(island.id));
+		assert_eq!(island.area, 16 * 16);
+		assert_eq!(island.bounds, URect::new(8, 8, 23, 23));
+
+		let sea = regions.region_at(&HexCoord::from_offset_pos(0, 0)).unwrap();
+		assert_eq!(sea.kind, RegionKind::Sea);
+		assert!(sea.neighbors.contains(&island.id));
+		assert!(island.neighbors.contains(&sea.id));
+		assert!(regions.is_water_connected(&HexCoord::from_offset_pos(0, 0), &HexCoord::from_offset_pos(63, 63)));
+
+		let names: Vec<_> = regions.regions.iter().map(|r| r.name.as_str()).collect();
+		assert!(names.iter().all(|n| !n.is_empty()));
+		for (i, name) in names.iter().enumerate() {
+			assert!(!names[i + 1..].contains(name), "Duplicate region name {}", name);
+		}
+	}
+
+	#[test]
+	fn separate_lakes()
+	{
+		let map = create_map(|x, y| {
+			if (4..10).contains(&x) && (4..10).contains(&y) || (30..36).contains(&x) && (4..10).contains(&y) {
+				return 0.;
+			}
+			return 10.;
+		});
+		let regions = generate(&map);
+
+		assert_eq!(regions.iter_kind(RegionKind::Lake).count(), 2);
+		let a = HexCoord::from_offset_pos(6, 6);
+		let b = HexCoord::from_offset_pos(32, 6);
+		assert!(regions.is_water_connected(&a, &HexCoord::from_offset_pos(7, 7)));
+		assert!(!regions.is_water_connected(&a, &b));
+		assert!(!regions.is_water_connected(&a, &HexCoord::from_offset_pos(20, 20)));
+	}
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+use rayon::prelude::*;
+
+use super::map::Map;
+
+/// Cached [`Map::slope_at`] of every tile, row major
+#[derive(Resource, Clone)]
+pub struct SlopeMap
+{
+	slopes: Vec<f32>,
+	width: usize,
+	height: usize,
+}
+
+impl SlopeMap
+{
+	pub fn build(map: &Map) -> Self
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Build Slope Map").entered();
+		let width = map.get_tile_width();
+		let slopes = (0..map.get_tile_count())
+			.into_par_iter()
+			.map(|i| map.slope_at(&HexCoord::from_offset_pos(i % width, i / width)))
+			.collect();
+		return Self {
+			slopes,
+			width,
+			height: map.get_tile_height(),
+		};
+	}
+
+	/// Recalculates the slope of the changed tiles and their neighbors
+	pub fn update_tiles(&mut self, map: &Map, tiles: &[HexCoord])
+	{
+		for tile in tiles {
+			self.update_tile(map, tile);
+			for n in tile.get_neighbors() {
+				self.update_tile(map, &n);
+			}
+		}
+	}
+
+	fn update_tile(&mut self, map: &Map, tile: &HexCoord)
+	{
+		if !self.is_in_bounds(tile) {
+			return;
+		}
+		self.slopes[tile.to_index(self.width)] = map.slope_at(tile);
+	}
+
+	pub fn get(&self, pos: &HexCoord) -> f32
+	{
+		assert!(
+			self.is_in_bounds(pos),
+			"The provided coordinate is not within the map bounds"
+		);
+		return self.slopes[pos.to_index(self.width)];
+	}
+
+	pub fn is_in_bounds(&self, pos: &HexCoord) -> bool
+	{
+		return pos.is_in_bounds(self.height, self.width);
+	}
+
+	/// Gets the slope of every tile, row major
+	pub fn get_slopes(&self) -> &[f32]
+	{
+		return &self.slopes;
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn update_on_change()
+	{
+		let mut map = Map {
+			chunks: vec![Chunk::default()],
+			height: 1,
+			width: 1,
+			sealevel: 0.,
+			min_level: 0.,
+			max_level: 0.,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+		let mut slopes = SlopeMap::build(&map);
+		let pos = HexCoord::from_offset_pos(20, 20);
+		assert!(slopes.get_slopes().iter().all(|s| *s == 0.));
+
+		map.set_height(&pos, 3.);
+		slopes.update_tiles(&map, &[pos]);
+		assert_eq!(slopes.get(&pos), 3.);
+		for n in pos.get_neighbors() {
+			assert_eq!(slopes.get(&n), 3.);
+		}
+		assert_eq!(slopes.get(&HexCoord::from_offset_pos(25, 20)), 0.);
+		assert_eq!(slopes.get_slopes(), SlopeMap::build(&map).get_slopes());
+	}
+}
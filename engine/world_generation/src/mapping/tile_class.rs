@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+use rayon::prelude::*;
+
+use super::map::Map;
+
+/// Terrain class of a tile, the shared definition of what counts as water and land
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TileClass
+{
+	/// More than [`TileClass::SHALLOW_DEPTH`] below the sea level
+	DeepWater,
+	/// At or below the sea level
+	ShallowWater,
+	/// Land with at least one water neighbor
+	Shore,
+	#[default]
+	Land,
+	/// Land with a neighbor more than [`TileClass::CLIFF_HEIGHT`] higher or lower
+	Cliff,
+	/// Land in the top part of the height range above the sea level, see [`TileClass::PEAK_HEIGHT`]
+	Peak,
+}
+
+impl TileClass
+{
+	/// Depth below the sea level where shallow water turns into deep water
+	pub const SHALLOW_DEPTH: f32 = 1.;
+	/// Height difference to a neighbor that makes a tile a cliff
+	pub const CLIFF_HEIGHT: f32 = 2.;
+	/// Fraction of the height range between the sea level and the highest point above which tiles are peaks
+	pub const PEAK_HEIGHT: f32 = 0.8;
+
+	/// Classifies a tile from its height and the heights of its neighbors, `None` for neighbors out of bounds.
+	///
+	/// Water only depends on the height, shore takes priority over cliffs and cliffs over peaks.
+	pub fn classify(height: f32, sealevel: f32, peak_level: f32, neighbors: &[Option<f32>; 6]) -> TileClass
+	{
+		if height <= sealevel {
+			if sealevel - height > Self::SHALLOW_DEPTH {
+				return TileClass::DeepWater;
+			}
+			return TileClass::ShallowWater;
+		}
+		let neighbors = neighbors.iter().flatten();
+		if neighbors.clone().any(|n| *n <= sealevel) {
+			return TileClass::Shore;
+		}
+		if neighbors.clone().any(|n| (n - height).abs() > Self::CLIFF_HEIGHT) {
+			return TileClass::Cliff;
+		}
+		if height >= peak_level {
+			return TileClass::Peak;
+		}
+		return TileClass::Land;
+	}
+
+	pub fn is_water(&self) -> bool
+	{
+		return matches!(self, TileClass::DeepWater | TileClass::ShallowWater);
+	}
+
+	pub fn is_land(&self) -> bool
+	{
+		return !self.is_water();
+	}
+
+	/// Whether the tile is flat land that buildings can be placed on
+	pub fn is_buildable(&self) -> bool
+	{
+		return matches!(self, TileClass::Shore | TileClass::Land);
+	}
+}
+
+/// Tile classes of every chunk, the [`Map`] keeps them up to date with [`Map::update_tile_classes`]
+#[derive(Clone, Default)]
+pub struct TileClassMap
+{
+	chunks: Vec<[TileClass; Chunk::AREA]>,
+	width: usize,
+	height: usize,
+}
+
+impl TileClassMap
+{
+	pub fn build(map: &Map) -> Self
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Build Tile Classes").entered();
+		let chunks = (0..map.chunks.len())
+			.into_par_iter()
+			.map(|index| map.get_chunk_tile_classes(index))
+			.collect();
+		return Self {
+			chunks,
+			width: map.width,
+			height: map.height,
+		};
+	}
+
+	pub fn set_chunk(&mut self, index: usize, classes: [TileClass; Chunk::AREA])
+	{
+		self.chunks[index] = classes;
+	}
+
+	/// Whether the classes have not been built yet
+	pub fn is_empty(&self) -> bool
+	{
+		return self.chunks.is_empty();
+	}
+
+	pub fn get(&self, pos: &HexCoord) -> TileClass
+	{
+		assert!(
+			pos.is_in_bounds(self.height * Chunk::SIZE, self.width * Chunk::SIZE),
+			"The provided coordinate is not within the map bounds"
+		);
+		return self.chunks[pos.to_chunk_index(self.width)][pos.to_chunk_local_index()];
+	}
+
+	pub fn get_chunk(&self, index: usize) -> &[TileClass; Chunk::AREA]
+	{
+		return &self.chunks[index];
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn classify()
+	{
+		let flat = [Some(5.); 6];
+		assert_eq!(TileClass::classify(0., 2., 8., &flat), TileClass::DeepWater);
+		assert_eq!(TileClass::classify(1.5, 2., 8., &flat), TileClass::ShallowWater);
+		assert_eq!(TileClass::classify(2., 2., 8., &flat), TileClass::ShallowWater);
+		assert_eq!(TileClass::classify(5., 2., 8., &flat), TileClass::Land);
+		assert_eq!(TileClass::classify(6., 2., 5.5, &flat), TileClass::Peak);
+
+		let mut steep = flat;
+		steep[3] = Some(8.);
+		assert_eq!(TileClass::classify(5., 2., 8., &steep), TileClass::Cliff);
+		steep[1] = Some(1.);
+		assert_eq!(TileClass::classify(5., 2., 8., &steep), TileClass::Shore);
+
+		let edge = [None, None, None, Some(5.), Some(5.), None];
+		assert_eq!(TileClass::classify(5., 2., 8., &edge), TileClass::Land);
+	}
+
+	#[test]
+	fn predicates()
+	{
+		assert!(TileClass::DeepWater.is_water());
+		assert!(TileClass::ShallowWater.is_water());
+		for class in [TileClass::Shore, TileClass::Land, TileClass::Cliff, TileClass::Peak] {
+			assert!(class.is_land());
+			assert!(!class.is_water());
+		}
+		assert!(TileClass::Shore.is_buildable());
+		assert!(TileClass::Land.is_buildable());
+		assert!(!TileClass::Cliff.is_buildable());
+		assert!(!TileClass::ShallowWater.is_buildable());
+	}
+
+	#[test]
+	fn map_predicates()
+	{
+		//Heights rise by 0.5 per column, sea level between column 4 and 5
+		let mut chunk = Chunk::default();
+		for i in 0..Chunk::AREA {
+			chunk.heights[i] = (i % Chunk::SIZE) as f32 * 0.5;
+		}
+		let mut map = Map {
+			chunks: vec![chunk],
+			height: 1,
+			width: 1,
+			sealevel: 2.2,
+			min_level: 0.,
+			max_level: 31.5,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+		let classes = TileClassMap::build(&map);
+
+		let deep = HexCoord::from_offset_pos(0, 10);
+		let shallow = HexCoord::from_offset_pos(4, 10);
+		let shore = HexCoord::from_offset_pos(5, 10);
+		let land = HexCoord::from_offset_pos(20, 10);
+		let peak = HexCoord::from_offset_pos(63, 10);
+		assert_eq!(classes.get(&deep), TileClass::DeepWater);
+		assert_eq!(classes.get(&shallow), TileClass::ShallowWater);
+		assert_eq!(classes.get(&shore), TileClass::Shore);
+		assert_eq!(classes.get(&land), TileClass::Land);
+		assert_eq!(classes.get(&peak), TileClass::Peak);
+
+		map.build_tile_classes();
+		assert!(map.is_underwater(&deep) && map.is_underwater(&shallow));
+		assert!(!map.is_on_land(&shallow));
+		assert!(map.is_on_land(&shore) && map.is_on_land(&peak));
+		assert!(!map.is_underwater(&land));
+		for y in 0..Chunk::SIZE {
+			for x in 0..Chunk::SIZE {
+				let pos = HexCoord::from_offset_pos(x, y);
+				assert_eq!(map.get_tile_class(&pos), classes.get(&pos));
+				assert_eq!(map.is_underwater(&pos), !map.is_on_land(&pos));
+			}
+		}
+
+		//The predicates read the cache until the chunk is reclassified
+		map.set_height(&land, 0.);
+		assert!(map.is_on_land(&land));
+		map.update_tile_classes(&[0]);
+		assert!(map.is_underwater(&land));
+	}
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use bevy::{
+	asset::RenderAssetUsages,
+	image::{ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
+	prelude::*,
+	render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension},
+};
+use image::{DynamicImage, RgbaImage, imageops::FilterType};
+
+/// Assembles individual images into the layers of an array texture
+pub struct TextureAtlasBuilder
+{
+	size: u32,
+	layers: Vec<RgbaImage>,
+	keys: HashMap<String, u32>,
+}
+
+impl TextureAtlasBuilder
+{
+	/// `size` is the width and height of every layer, images of other sizes are resized to fit
+	pub fn new(size: u32) -> Self
+	{
+		assert!(size != 0, "Layer size cannot be zero");
+		return Self {
+			size,
+			layers: Vec::new(),
+			keys: HashMap::new(),
+		};
+	}
+
+	/// Adds an image as a new layer and returns its index, images added with the same key share a layer
+	pub fn add(&mut self, key: &str, image: &DynamicImage) -> u32
+	{
+		if let Some(layer) = self.keys.get(key) {
+			return *layer;
+		}
+		let mut layer = image.to_rgba8();
+		if layer.width() != self.size || layer.height() != self.size {
+			layer = image::imageops::resize(&layer, self.size, self.size, FilterType::Triangle);
+		}
+		let index = self.layers.len() as u32;
+		self.layers.push(layer);
+		self.keys.insert(key.to_string(), index);
+		return index;
+	}
+
+	/// Adds every square layer of a vertically stacked image in order, keyed `key#index`, returns the first layer
+	pub fn add_stacked(&mut self, key: &str, image: &DynamicImage) -> u32
+	{
+		let first = self.get_layer_count();
+		let size = image.width().max(1);
+		for i in 0..image.height() / size {
+			self.add(&format!("{}#{}", key, i), &image.crop_imm(0, i * size, size, size));
+		}
+		return first;
+	}
+
+	pub fn get_layer(&self, key: &str) -> Option<u32>
+	{
+		return self.keys.get(key).copied();
+	}
+
+	pub fn get_layer_count(&self) -> u32
+	{
+		return self.layers.len() as u32;
+	}
+
+	/// Number of mip levels down to 1x1
+	pub fn get_mip_level_count(&self) -> u32
+	{
+		return u32::BITS - self.size.leading_zeros();
+	}
+
+	/// Gets the pixels of every layer as rgba8, layer major with all mip levels of a layer after each other
+	pub fn build_data(&self, mipmaps: bool) -> Vec<u8>
+	{
+		#[cfg(feature = "tracing")]
+		let _span = info_span!("Build Atlas Data").entered();
+		let mip_levels = if mipmaps { self.get_mip_level_count() } else { 1 };
+		let mut data = Vec::new();
+		for layer in &self.layers {
+			let mut mip = layer.clone();
+			data.extend_from_slice(mip.as_raw());
+			for _ in 1..mip_levels {
+				let size = (mip.width() / 2).max(1);
+				mip = image::imageops::resize(&mip, size, size, FilterType::Triangle);
+				data.extend_from_slice(mip.as_raw());
+			}
+		}
+		return data;
+	}
+
+	/// Builds the array texture, an empty builder produces a single transparent layer
+	pub fn build(&self, mipmaps: bool) -> Image
+	{
+		let mut data = self.build_data(mipmaps);
+		let mut layer_count = self.get_layer_count();
+		if layer_count == 0 {
+			//Textures cannot be empty
+			let mut empty = TextureAtlasBuilder::new(self.size);
+			empty.add("", &DynamicImage::new_rgba8(self.size, self.size));
+			data = empty.build_data(mipmaps);
+			layer_count = 1;
+		}
+
+		let mut image = Image::default();
+		image.data = Some(data);
+		image.texture_descriptor.size = Extent3d {
+			width: self.size,
+			height: self.size,
+			depth_or_array_layers: layer_count,
+		};
+		image.texture_descriptor.dimension = TextureDimension::D2;
+		image.texture_descriptor.format = TextureFormat::Rgba8UnormSrgb;
+		image.texture_descriptor.mip_level_count = if mipmaps { self.get_mip_level_count() } else { 1 };
+		image.texture_view_descriptor = Some(TextureViewDescriptor {
+			dimension: Some(TextureViewDimension::D2Array),
+			..default()
+		});
+		image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+			mipmap_filter: ImageFilterMode::Linear,
+			..ImageSamplerDescriptor::linear()
+		});
+		image.asset_usage = RenderAssetUsages::RENDER_WORLD;
+		return image;
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use image::Rgba;
+
+	use super::*;
+
+	fn solid(size: u32, color: [u8; 4]) -> DynamicImage
+	{
+		return DynamicImage::ImageRgba8(RgbaImage::from_pixel(size, size, Rgba(color)));
+	}
+
+	#[test]
+	fn layers_and_mipmaps()
+	{
+		let mut builder = TextureAtlasBuilder::new(4);
+		assert_eq!(builder.add("grass.png", &solid(4, [0, 255, 0, 255])), 0);
+		assert_eq!(builder.add("rock.png", &solid(16, [128, 128, 128, 255])), 1);
+		assert_eq!(builder.add("grass.png", &solid(4, [255, 0, 0, 255])), 0);
+		assert_eq!(builder.get_layer("rock.png"), Some(1));
+		assert_eq!(builder.get_layer("sand.png"), None);
+		assert_eq!(builder.get_layer_count(), 2);
+		assert_eq!(builder.get_mip_level_count(), 3);
+
+		let data = builder.build_data(false);
+		assert_eq!(data.len(), 2 * 4 * 4 * 4);
+		assert_eq!(&data[..4], &[0, 255, 0, 255]);
+		assert_eq!(&data[64..68], &[128, 128, 128, 255]);
+
+		//4x4, 2x2 and 1x1 per layer
+		let data = builder.build_data(true);
+		assert_eq!(data.len(), 2 * (16 + 4 + 1) * 4);
+		assert_eq!(&data[80..84], &[0, 255, 0, 255]);
+		assert_eq!(&data[84..88], &[128, 128, 128, 255]);
+	}
+
+	#[test]
+	fn stacked_layers()
+	{
+		let mut stacked = RgbaImage::from_pixel(4, 12, Rgba([255, 0, 0, 255]));
+		for x in 0..4 {
+			for y in 4..8 {
+				stacked.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+			}
+		}
+		let mut builder = TextureAtlasBuilder::new(4);
+		assert_eq!(builder.add_stacked("atlas", &DynamicImage::ImageRgba8(stacked)), 0);
+		assert_eq!(builder.get_layer_count(), 3);
+		assert_eq!(builder.get_layer("atlas#1"), Some(1));
+		assert_eq!(builder.add("grass.png", &solid(4, [0, 255, 0, 255])), 3);
+
+		let data = builder.build_data(false);
+		assert_eq!(&data[..4], &[255, 0, 0, 255]);
+		assert_eq!(&data[64..68], &[0, 0, 255, 255]);
+		assert_eq!(&data[128..132], &[255, 0, 0, 255]);
+	}
+}
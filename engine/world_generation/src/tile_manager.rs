@@ -1,34 +1,220 @@
-use asset_loader::create_asset_loader;
-use bevy::{asset::Asset, reflect::TypePath};
+use asset_loader::{PhosAsset, error::ValidateAsset};
+use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+/// Registry of every tile type, maps the string identifiers of tiles to dense runtime ids
 #[derive(Resource, Default, Debug)]
 pub struct TileManager
 {
 	pub tiles: Vec<Handle<TileAsset>>,
+	identifiers: Vec<String>,
+	ids: HashMap<String, usize>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileRegistryError
+{
+	DuplicateIdentifier(String),
+	MissingTile(String),
+	TextureOutOfRange
+	{
+		identifier: String,
+		texture_id: u32,
+		layer_count: u32,
+	},
+}
+
+impl Display for TileRegistryError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			TileRegistryError::DuplicateIdentifier(identifier) => {
+				write!(f, "Tile '{}' is registered more than once", identifier)
+			}
+			TileRegistryError::MissingTile(identifier) => write!(f, "Tile '{}' does not exist", identifier),
+			TileRegistryError::TextureOutOfRange {
+				identifier,
+				texture_id,
+				layer_count,
+			} => write!(
+				f,
+				"Tile '{}' uses texture {} but the atlas only has {} layers",
+				identifier, texture_id, layer_count
+			),
+		};
+	}
+}
+
+impl std::error::Error for TileRegistryError {}
+
 impl TileManager
 {
-	pub fn register_tile(&mut self, tile: Handle<TileAsset>) -> usize
+	/// Registers a tile under `identifier`, returning its runtime id
+	pub fn register_tile(&mut self, identifier: &str, tile: Handle<TileAsset>) -> Result<usize, TileRegistryError>
 	{
+		if self.ids.contains_key(identifier) {
+			return Err(TileRegistryError::DuplicateIdentifier(identifier.to_string()));
+		}
 		let id = self.tiles.len();
 		self.tiles.push(tile);
-		return id;
+		self.identifiers.push(identifier.to_string());
+		self.ids.insert(identifier.to_string(), id);
+		return Ok(id);
+	}
+
+	/// Gets the runtime id of a tile from its identifier
+	pub fn get_id(&self, identifier: &str) -> Result<usize, TileRegistryError>
+	{
+		return self
+			.ids
+			.get(identifier)
+			.copied()
+			.ok_or_else(|| TileRegistryError::MissingTile(identifier.to_string()));
+	}
+
+	pub fn get_identifier(&self, id: usize) -> Option<&str>
+	{
+		return self.identifiers.get(id).map(|i| i.as_str());
+	}
+
+	pub fn get_tile<'a>(&self, id: usize, assets: &'a Assets<TileAsset>) -> Option<&'a TileAsset>
+	{
+		return self.tiles.get(id).and_then(|handle| assets.get(handle.id()));
+	}
+
+	/// Gets the move cost of every registered tile, indexed by tile id
+	pub fn get_move_costs(&self, assets: &Assets<TileAsset>) -> Vec<f32>
+	{
+		return (0..self.tiles.len())
+			.map(|id| self.get_tile(id, assets).map_or(1., |t| t.move_cost))
+			.collect();
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug, TypePath, Asset, Clone)]
+#[derive(Serialize, Deserialize, Debug, TypePath, Asset, Clone, PhosAsset)]
+#[asset(extensions = ["tile", "tile.ron", "tile.json", "tile.toml"])]
 pub struct TileAsset
 {
+	/// Runtime id assigned by the [`TileManager`], not stable between runs
 	#[serde(skip)]
 	pub id: usize,
+	/// Stable identifier other assets refer to the tile by, the file name is used when it is empty
+	#[serde(default)]
+	pub identifier: String,
 	pub name: String,
+	/// Layer of the tile atlas, assigned at load when `texture` is set
+	#[serde(default)]
 	pub texture_id: u32,
-	#[serde(skip)]
+	/// Path of the top texture, when empty `texture_id` has to point into a pre-stacked atlas
+	#[serde(default)]
 	pub texture: String,
-	pub side_texture_id: u32,
 	#[serde(skip)]
+	#[asset(path = "texture")]
+	pub texture_handle: Handle<Image>,
+	#[serde(default)]
+	pub side_texture_id: u32,
+	#[serde(default)]
 	pub side_texture: String,
+	#[serde(skip)]
+	#[asset(path = "side_texture")]
+	pub side_texture_handle: Handle<Image>,
+	/// Multiplier for how hard the tile is to move across
+	#[serde(default = "default_move_cost")]
+	pub move_cost: f32,
 }
 
-create_asset_loader!(TileAssetPlugin, TileAssetLoader, TileAsset, &["tile", "tile.ron"],;?);
+impl TileAsset
+{
+	/// Checks that both textures are layers of an atlas with `layer_count` layers
+	pub fn validate_textures(&self, layer_count: u32) -> Result<(), TileRegistryError>
+	{
+		for texture_id in [self.texture_id, self.side_texture_id] {
+			if texture_id >= layer_count {
+				return Err(TileRegistryError::TextureOutOfRange {
+					identifier: self.identifier.clone(),
+					texture_id,
+					layer_count,
+				});
+			}
+		}
+		return Ok(());
+	}
+}
+
+fn default_move_cost() -> f32
+{
+	return 1.;
+}
+
+impl ValidateAsset for TileAsset
+{
+	fn validate(&self) -> Result<(), String>
+	{
+		if !self.move_cost.is_finite() || self.move_cost < 0. {
+			return Err(format!("Move cost has to be positive, got {}", self.move_cost));
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn create_tile(identifier: &str, texture_id: u32, side_texture_id: u32) -> TileAsset
+	{
+		return TileAsset {
+			id: 0,
+			identifier: identifier.to_string(),
+			name: identifier.to_string(),
+			texture_id,
+			texture: String::new(),
+			texture_handle: Handle::default(),
+			side_texture_id,
+			side_texture: String::new(),
+			side_texture_handle: Handle::default(),
+			move_cost: 1.,
+		};
+	}
+
+	#[test]
+	fn register_and_resolve()
+	{
+		let mut manager = TileManager::default();
+		assert_eq!(manager.register_tile("grass", Handle::default()), Ok(0));
+		assert_eq!(manager.register_tile("sand", Handle::default()), Ok(1));
+		assert_eq!(
+			manager.register_tile("grass", Handle::default()),
+			Err(TileRegistryError::DuplicateIdentifier("grass".into()))
+		);
+		assert_eq!(manager.tiles.len(), 2);
+		assert_eq!(manager.get_id("sand"), Ok(1));
+		assert_eq!(manager.get_identifier(0), Some("grass"));
+		assert_eq!(
+			manager.get_id("snow"),
+			Err(TileRegistryError::MissingTile("snow".into()))
+		);
+	}
+
+	#[test]
+	fn texture_range()
+	{
+		assert!(create_tile("grass", 0, 3).validate_textures(4).is_ok());
+		let err = create_tile("rock", 1, 4).validate_textures(4).unwrap_err();
+		assert_eq!(
+			err,
+			TileRegistryError::TextureOutOfRange {
+				identifier: "rock".into(),
+				texture_id: 4,
+				layer_count: 4,
+			}
+		);
+		assert_eq!(
+			err.to_string(),
+			"Tile 'rock' uses texture 4 but the atlas only has 4 layers"
+		);
+	}
+}
@@ -1,4 +1,4 @@
-use asset_loader::create_asset_loader;
+use asset_loader::{PhosAsset, error::ValidateAsset};
 use bevy::prelude::*;
 use bevy::{asset::Asset, reflect::TypePath};
 use serde::{Deserialize, Serialize};
@@ -7,12 +7,71 @@ use crate::tile_manager::TileAsset;
 
 pub struct TileMapper;
 
-#[derive(Serialize, Deserialize, Debug, TypePath, Asset)]
+#[derive(Serialize, Deserialize, Debug, TypePath, Asset, PhosAsset)]
+#[asset(extensions = ["mapper", "mapper.ron", "mapper.json", "mapper.toml"])]
 pub struct TileMapperAsset {
 	#[serde(skip)]
+	#[asset(path = "tiles_path")]
 	pub tiles: Vec<Handle<TileAsset>>,
 	pub tiles_path: Vec<String>,
 	pub thresholds: Vec<f32>,
+	/// Checked in order before the height thresholds, the first rule that matches picks the tile
+	#[serde(default)]
+	pub rules: Vec<TileMapperRule>,
+	/// How far noise shifts the height used for the thresholds, breaks up the horizontal bands between tiles
+	#[serde(default)]
+	pub threshold_noise: f32,
+}
+
+/// Conditions a tile has to meet to be painted with [`TileMapperRule::tile`], unset conditions always match
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TileMapperRule {
+	/// Index into `tiles_path`
+	pub tile: usize,
+	pub min_height: Option<f32>,
+	pub max_height: Option<f32>,
+	/// Largest height difference to a neighbor, see `Map::slope_at`
+	pub min_slope: Option<f32>,
+	pub max_slope: Option<f32>,
+	/// Distance in tiles to the closest water tile, water tiles are 0
+	pub max_water_distance: Option<usize>,
+	/// Moisture of the biome data, between 0 and 100
+	pub min_moisture: Option<f32>,
+	pub max_moisture: Option<f32>,
+	/// How far noise shifts the height before it is compared to the height limits
+	pub noise: f32,
+}
+
+/// The terrain around a tile, used to evaluate [`TileMapperRule`]s
+#[derive(Debug, Clone, Default)]
+pub struct TileSample {
+	pub height: f32,
+	pub slope: f32,
+	/// `None` when there is no water within [`TileMapperAsset::get_water_distance_range`]
+	pub water_distance: Option<usize>,
+	pub moisture: f32,
+	/// Between -1 and 1
+	pub noise: f32,
+}
+
+impl TileMapperRule {
+	pub fn matches(&self, sample: &TileSample) -> bool {
+		let height = sample.height + sample.noise * self.noise;
+		let in_range = |value: f32, min: Option<f32>, max: Option<f32>| {
+			return min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max);
+		};
+		if !in_range(height, self.min_height, self.max_height)
+			|| !in_range(sample.slope, self.min_slope, self.max_slope)
+			|| !in_range(sample.moisture, self.min_moisture, self.max_moisture)
+		{
+			return false;
+		}
+		if let Some(max) = self.max_water_distance {
+			return sample.water_distance.is_some_and(|d| d <= max);
+		}
+		return true;
+	}
 }
 
 impl TileMapperAsset {
@@ -25,13 +84,116 @@ impl TileMapperAsset {
 		}
 		return self.tiles.last().unwrap().id();
 	}
+
+	/// Picks a tile with the rules, falling back to the height thresholds
+	pub fn sample_tile_with_rules(&self, sample: &TileSample) -> AssetId<TileAsset> {
+		for rule in &self.rules {
+			if !rule.matches(sample) {
+				continue;
+			}
+			if let Some(tile) = self.tiles.get(rule.tile) {
+				return tile.id();
+			}
+			warn!("Tile mapper rule uses tile {} but only {} tiles exist", rule.tile, self.tiles.len());
+		}
+		return self.sample_tile(sample.height + sample.noise * self.threshold_noise);
+	}
+
+	/// Whether sampling needs more than the height, see [`TileMapperAsset::sample_tile_with_rules`]
+	pub fn has_rules(&self) -> bool {
+		return !self.rules.is_empty() || self.threshold_noise != 0.;
+	}
+
+	/// Furthest distance to water any rule checks
+	pub fn get_water_distance_range(&self) -> usize {
+		return self.rules.iter().filter_map(|r| r.max_water_distance).max().unwrap_or(0);
+	}
 }
 
-create_asset_loader!(
-	TileMapperAssetPlugin,
-	TileMapperAssetLoader,
-	TileMapperAsset,
-	&["mapper", "mapper.ron"],;
-	tiles_path -> tiles
-	?
-);
+impl ValidateAsset for TileMapperAsset {
+	fn validate(&self) -> Result<(), String> {
+		if self.tiles_path.is_empty() {
+			return Err("No tiles defined".to_string());
+		}
+		if self.thresholds.len() > self.tiles_path.len() {
+			return Err(format!(
+				"{} thresholds but only {} tiles",
+				self.thresholds.len(),
+				self.tiles_path.len()
+			));
+		}
+		if let Some(rule) = self.rules.iter().find(|r| r.tile >= self.tiles_path.len()) {
+			return Err(format!(
+				"Rule uses tile {} but only {} tiles exist",
+				rule.tile,
+				self.tiles_path.len()
+			));
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn validate() {
+		let mut mapper = TileMapperAsset {
+			tiles: Vec::new(),
+			tiles_path: vec!["sand.tile".into(), "grass.tile".into()],
+			thresholds: vec![5., 20.],
+			rules: vec![TileMapperRule { tile: 1, ..default() }],
+			threshold_noise: 0.,
+		};
+		assert!(mapper.validate().is_ok());
+		mapper.rules[0].tile = 2;
+		assert!(mapper.validate().is_err());
+		mapper.rules.clear();
+		mapper.thresholds.push(40.);
+		assert!(mapper.validate().is_err());
+		mapper.tiles_path.clear();
+		mapper.thresholds.clear();
+		assert!(mapper.validate().is_err());
+	}
+
+	#[test]
+	fn rule_matching() {
+		let rock = TileMapperRule {
+			min_slope: Some(2.),
+			..default()
+		};
+		let sand = TileMapperRule {
+			max_height: Some(10.),
+			max_water_distance: Some(2),
+			..default()
+		};
+		let snow = TileMapperRule {
+			min_height: Some(50.),
+			noise: 2.,
+			..default()
+		};
+		let mut sample = TileSample {
+			height: 9.,
+			slope: 0.5,
+			water_distance: Some(1),
+			..default()
+		};
+		assert!(!rock.matches(&sample));
+		assert!(sand.matches(&sample));
+		sample.water_distance = Some(3);
+		assert!(!sand.matches(&sample));
+		sample.water_distance = None;
+		assert!(!sand.matches(&sample));
+		sample.slope = 4.;
+		assert!(rock.matches(&sample));
+
+		sample.height = 49.;
+		assert!(!snow.matches(&sample));
+		sample.noise = 0.6;
+		assert!(snow.matches(&sample));
+		sample.noise = -0.6;
+		sample.height = 50.5;
+		assert!(!snow.matches(&sample));
+	}
+}
@@ -1,11 +1,14 @@
-use asset_loader::create_asset_loader;
+use asset_loader::{PhosAsset, error::ValidateAsset};
 use bevy::{
 	ecs::relationship::RelatedSpawnerCommands,
 	gltf::{GltfMesh, GltfNode},
 	prelude::*,
 };
+use hex::prelude::*;
+use placement_validation::placement_rule::PlacementRule;
 use serde::{Deserialize, Serialize};
-use shared::{component_defination::ComponentDefination, identifiers::ResourceIdentifier};
+use shared::{component_defination::ComponentDefination, identifiers::ResourceAmount};
+use world_generation::mapping::map::Map;
 
 use crate::{
 	buildings::{
@@ -17,22 +20,42 @@ use crate::{
 	prelude::Building,
 };
 
-#[derive(Asset, TypePath, Debug, Serialize, Deserialize)]
+#[derive(Asset, TypePath, Debug, Serialize, Deserialize, PhosAsset)]
+#[asset(extensions = ["building", "building.ron", "building.json", "building.toml"])]
 pub struct BuildingAsset
 {
+	/// Key other content refers to the building by, the file name is used when it is empty
+	#[serde(default)]
+	pub identifier: String,
 	pub name: String,
 	pub description: String,
 	pub footprint: BuildingFootprint,
+	/// Where the building may be placed, every tile of the footprint has to pass
+	#[serde(default)]
+	pub placement: PlacementRule,
 	pub prefab_path: String,
 	#[serde(skip)]
+	#[asset(path = "prefab_path")]
 	pub prefab: Handle<Gltf>,
 	pub base_mesh_path: String,
 
-	pub cost: Vec<ResourceIdentifier>,
-	pub consumption: Vec<ResourceIdentifier>,
-	pub production: Vec<ResourceIdentifier>,
+	pub cost: Vec<ResourceAmount>,
+	pub consumption: Vec<ResourceAmount>,
+	pub production: Vec<ResourceAmount>,
+	/// Resources the colony starts with when this building is placed as the headquarters
+	#[serde(default)]
+	pub starting_resources: Vec<ResourceAmount>,
 
 	pub health: u32,
+	/// Seconds it takes to construct the building at normal build speed, it is finished right away when this is 0
+	#[serde(default)]
+	pub build_time: f32,
+	/// Builder drones the building provides once it is finished
+	#[serde(default)]
+	pub builder_drones: u32,
+	/// Seconds it takes to tear the building down, it is removed right away when this is 0
+	#[serde(default)]
+	pub demolition_time: f32,
 
 	pub building_type: BuildingType,
 	pub components: Option<Vec<ComponentDefination>>,
@@ -40,6 +63,14 @@ pub struct BuildingAsset
 
 impl BuildingAsset
 {
+	/// Runs the placement rule across a footprint, the error explains which tile failed
+	pub fn validate_placement(&self, footprint: &[HexCoord], map: &Map) -> Result<(), String>
+	{
+		return self.placement.validate_footprint(footprint, map).map_err(|coord| {
+			return format!("{} has to be placed {}, {} is not", self.name, self.placement, coord);
+		});
+	}
+
 	#[allow(unused)]
 	pub fn spawn(
 		&self,
@@ -144,11 +175,25 @@ pub enum BuildingType
 	Tech(TechBuildingInfo),
 }
 
-create_asset_loader!(
-	BuildingAssetPlugin,
-	BuildingAssetLoader,
-	BuildingAsset,
-	&["building", "building.ron"],
-	prefab_path -> prefab
-	;?
-);
+impl ValidateAsset for BuildingAsset
+{
+	fn validate(&self) -> Result<(), String>
+	{
+		if self.name.is_empty() {
+			return Err("Building has no name".to_string());
+		}
+		if self.base_mesh_path.is_empty() {
+			return Err(format!("Building '{}' has no base_mesh_path", self.name));
+		}
+		if self.health == 0 {
+			return Err(format!("Building '{}' has no health", self.name));
+		}
+		if !self.build_time.is_finite() || self.build_time < 0. {
+			return Err(format!("Building '{}' has an invalid build_time", self.name));
+		}
+		if !self.demolition_time.is_finite() || self.demolition_time < 0. {
+			return Err(format!("Building '{}' has an invalid demolition_time", self.name));
+		}
+		return Ok(());
+	}
+}
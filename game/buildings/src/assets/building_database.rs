@@ -3,9 +3,83 @@ use bevy::{asset::Handle, prelude::Resource};
 use super::building_asset::BuildingAsset;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use shared::{
+	building::BuildingIdentifier,
+	content_registry::{ContentError, ContentRegistry, get_key_from_path},
+};
 
 #[derive(Resource, AssetCollection)]
 pub struct BuildingDatabase {
 	#[asset(key = "buildings", collection(typed))]
 	pub buildings: Vec<Handle<BuildingAsset>>,
+	/// Buildings of the content packs in load order, see [`shared::content_pack::ContentPacks`]
+	#[asset(key = "pack_buildings", collection(typed))]
+	pub pack_buildings: Vec<Handle<BuildingAsset>>,
+}
+
+impl BuildingDatabase {
+	/// Builds the lookup from building keys to [`BuildingIdentifier`]s, buildings that are not loaded are skipped
+	///
+	/// Buildings of content packs replace buildings with the same key
+	pub fn create_lookup(
+		&self,
+		assets: &Assets<BuildingAsset>,
+		asset_server: &AssetServer,
+	) -> Result<BuildingLookup, ContentError> {
+		let get_entry = |handle: &Handle<BuildingAsset>| {
+			let asset = assets.get(handle.id())?;
+			let key = if asset.identifier.is_empty() {
+				asset_server
+					.get_path(handle.id())
+					.and_then(|path| get_key_from_path(path.path()))
+					.unwrap_or_else(|| asset.name.clone())
+			} else {
+				asset.identifier.clone()
+			};
+			return Some((key, handle.clone()));
+		};
+		return Ok(BuildingLookup {
+			registry: ContentRegistry::build_with_overrides(
+				"building",
+				self.buildings.iter().filter_map(get_entry),
+				self.pack_buildings.iter().filter_map(get_entry),
+			)?,
+		});
+	}
+}
+
+/// Resolves the building keys of content files to [`BuildingIdentifier`]s
+#[derive(Resource)]
+pub struct BuildingLookup {
+	registry: ContentRegistry<BuildingAsset>,
+}
+
+impl BuildingLookup {
+	pub fn get_id(&self, key: &str) -> Result<BuildingIdentifier, ContentError> {
+		return Ok(BuildingIdentifier(self.registry.get_id(key)?));
+	}
+
+	/// Resolves every key, the errors list all unknown keys
+	pub fn get_ids<'a>(
+		&self,
+		keys: impl IntoIterator<Item = &'a str>,
+	) -> Result<Vec<BuildingIdentifier>, Vec<ContentError>> {
+		return Ok(self.registry.get_ids(keys)?.into_iter().map(BuildingIdentifier).collect());
+	}
+
+	pub fn get_key(&self, id: BuildingIdentifier) -> Option<&str> {
+		return self.registry.get_key(id.0);
+	}
+
+	pub fn get_handle(&self, id: BuildingIdentifier) -> Option<&Handle<BuildingAsset>> {
+		return self.registry.get_handle(id.0);
+	}
+
+	pub fn len(&self) -> usize {
+		return self.registry.len();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.registry.is_empty();
+	}
 }
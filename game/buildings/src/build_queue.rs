@@ -1,16 +1,262 @@
-use bevy::prelude::Resource;
+use bevy::prelude::*;
 use hex::prelude::*;
 use shared::building::BuildingIdentifier;
 
+use crate::construction::{CancelConstruction, UnderConstruction};
+
+/// Buildings the player ordered, highest priority first.
+/// Entries stay in the queue until their construction site is finished or cancelled
 #[derive(Resource, Default)]
 pub struct BuildQueue
 {
-	pub queue: Vec<QueueEntry>,
+	queue: Vec<QueueEntry>,
+	next_id: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QueueEntryId(pub u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueEntryState
+{
+	/// No construction site has been started yet
+	Queued,
+	InProgress,
+	Paused,
+	WaitingForResources,
+	/// The site could not be started, it is retried every frame
+	Blocked(String),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug)]
 pub struct QueueEntry
 {
+	id: QueueEntryId,
+	priority: i32,
+	pub building: BuildingIdentifier,
+	pub pos: HexCoord,
+	/// Sixths of a full turn, applied to the footprint and the spawned prefab
+	pub rotation: i32,
+	pub state: QueueEntryState,
+	/// Construction site of the entry, once it is started
+	pub site: Option<Entity>,
+}
+
+impl QueueEntry
+{
+	pub fn id(&self) -> QueueEntryId
+	{
+		return self.id;
+	}
+
+	pub fn priority(&self) -> i32
+	{
+		return self.priority;
+	}
+}
+
+/// Cancels a queue entry, its construction site is cancelled as well
+#[derive(Message)]
+pub struct CancelQueueEntry
+{
+	pub id: QueueEntryId,
+}
+
+/// Sent when a queue entry is dropped because the building can't be placed there
+#[derive(Message)]
+pub struct QueueEntryRejected
+{
+	pub id: QueueEntryId,
 	pub building: BuildingIdentifier,
 	pub pos: HexCoord,
+	pub reason: String,
+}
+
+impl BuildQueue
+{
+	/// Adds a building after every entry with the same or a higher priority
+	pub fn push(&mut self, building: BuildingIdentifier, pos: HexCoord, rotation: i32, priority: i32) -> QueueEntryId
+	{
+		let id = QueueEntryId(self.next_id);
+		self.next_id += 1;
+		let index = self.queue.partition_point(|entry| entry.priority >= priority);
+		self.queue.insert(
+			index,
+			QueueEntry {
+				id,
+				priority,
+				building,
+				pos,
+				rotation,
+				state: QueueEntryState::Queued,
+				site: None,
+			},
+		);
+		return id;
+	}
+
+	pub fn get(&self, id: QueueEntryId) -> Option<&QueueEntry>
+	{
+		return self.queue.iter().find(|entry| entry.id == id);
+	}
+
+	pub fn get_mut(&mut self, id: QueueEntryId) -> Option<&mut QueueEntry>
+	{
+		return self.queue.iter_mut().find(|entry| entry.id == id);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &QueueEntry>
+	{
+		return self.queue.iter();
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut QueueEntry>
+	{
+		return self.queue.iter_mut();
+	}
+
+	pub fn len(&self) -> usize
+	{
+		return self.queue.len();
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		return self.queue.is_empty();
+	}
+
+	/// Removes an entry, the caller is responsible for cancelling its construction site
+	pub fn remove(&mut self, id: QueueEntryId) -> Option<QueueEntry>
+	{
+		let index = self.index_of(id)?;
+		return Some(self.queue.remove(index));
+	}
+
+	/// Removes every entry the predicate returns false for
+	pub fn retain(&mut self, f: impl FnMut(&QueueEntry) -> bool)
+	{
+		self.queue.retain(f);
+	}
+
+	/// Changes the priority of an entry, it goes after the entries that already have that priority
+	pub fn set_priority(&mut self, id: QueueEntryId, priority: i32) -> bool
+	{
+		let Some(index) = self.index_of(id) else {
+			return false;
+		};
+		let mut entry = self.queue.remove(index);
+		entry.priority = priority;
+		let index = self.queue.partition_point(|entry| entry.priority >= priority);
+		self.queue.insert(index, entry);
+		return true;
+	}
+
+	/// Moves an entry to an index, it takes the priority of the entries around it so the queue stays ordered
+	pub fn move_to(&mut self, id: QueueEntryId, index: usize) -> bool
+	{
+		let Some(old_index) = self.index_of(id) else {
+			return false;
+		};
+		let mut entry = self.queue.remove(old_index);
+		let index = index.min(self.queue.len());
+		let before = index.checked_sub(1).map(|i| self.queue[i].priority);
+		let after = self.queue.get(index).map(|entry| entry.priority);
+		entry.priority = match (before, after) {
+			(Some(before), Some(after)) => entry.priority.clamp(after, before),
+			(Some(before), None) => entry.priority.min(before),
+			(None, Some(after)) => entry.priority.max(after),
+			(None, None) => entry.priority,
+		};
+		self.queue.insert(index, entry);
+		return true;
+	}
+
+	fn index_of(&self, id: QueueEntryId) -> Option<usize>
+	{
+		return self.queue.iter().position(|entry| entry.id == id);
+	}
+}
+
+pub fn cancel_queue_entries(
+	mut requests: MessageReader<CancelQueueEntry>,
+	mut queue: ResMut<BuildQueue>,
+	mut cancel: MessageWriter<CancelConstruction>,
+)
+{
+	for request in requests.read() {
+		if let Some(entry) = queue.remove(request.id)
+			&& entry.site.is_some()
+		{
+			cancel.write(CancelConstruction { coord: entry.pos });
+		}
+	}
+}
+
+/// Mirrors the state of the construction sites, entries whose site is gone are finished or cancelled
+pub fn update_build_queue(mut queue: ResMut<BuildQueue>, sites: Query<&UnderConstruction>)
+{
+	queue.retain(|entry| entry.site.is_none_or(|site| sites.contains(site)));
+	for entry in queue.iter_mut() {
+		let Some(site) = entry.site.and_then(|site| sites.get(site).ok()) else {
+			continue;
+		};
+		entry.state = if site.paused {
+			QueueEntryState::Paused
+		} else if site.waiting_for_resources {
+			QueueEntryState::WaitingForResources
+		} else {
+			QueueEntryState::InProgress
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn get_order(queue: &BuildQueue) -> Vec<u32>
+	{
+		return queue.iter().map(|entry| entry.id().0).collect();
+	}
+
+	#[test]
+	fn priorities()
+	{
+		let mut queue = BuildQueue::default();
+		let building = BuildingIdentifier(0);
+		let a = queue.push(building, HexCoord::new(0, 0), 0, 0);
+		let b = queue.push(building, HexCoord::new(1, 0), 0, 5);
+		let c = queue.push(building, HexCoord::new(2, 0), 0, 0);
+		assert_eq!(get_order(&queue), [b.0, a.0, c.0]);
+
+		assert!(queue.set_priority(c, 5));
+		assert_eq!(get_order(&queue), [b.0, c.0, a.0]);
+
+		assert!(queue.remove(b).is_some());
+		assert!(!queue.set_priority(b, 1));
+		assert_eq!(get_order(&queue), [c.0, a.0]);
+	}
+
+	#[test]
+	fn reordering()
+	{
+		let mut queue = BuildQueue::default();
+		let building = BuildingIdentifier(0);
+		let a = queue.push(building, HexCoord::new(0, 0), 0, 2);
+		let b = queue.push(building, HexCoord::new(1, 0), 0, 1);
+		let c = queue.push(building, HexCoord::new(2, 0), 0, 0);
+
+		assert!(queue.move_to(c, 0));
+		assert_eq!(get_order(&queue), [c.0, a.0, b.0]);
+		assert_eq!(queue.get(c).unwrap().priority(), 2);
+
+		assert!(queue.move_to(a, 10));
+		assert_eq!(get_order(&queue), [c.0, b.0, a.0]);
+		assert_eq!(queue.get(a).unwrap().priority(), 1);
+
+		//The priorities still match the order, new entries go to the right place
+		let d = queue.push(building, HexCoord::new(3, 0), 0, 1);
+		assert_eq!(get_order(&queue), [c.0, b.0, a.0, d.0]);
+	}
 }
@@ -1,27 +1,37 @@
-use bevy::{
-	ecs::world::CommandQueue,
-	gltf::{GltfMesh, GltfNode},
-	prelude::*,
-};
+use bevy::{ecs::world::CommandQueue, prelude::*};
 use bevy_asset_loader::loading_state::{
 	LoadingStateAppExt,
 	config::{ConfigureLoadingState, LoadingStateConfig},
 };
+use resources::resource_asset::ResourceLookup;
 use shared::{
 	despawn::Despawn,
-	events::TileModifiedEvent,
+	events::{BuildingRemoved, TileModifiedEvent},
 	resources::TileUnderCursor,
 	states::{AssetLoadState, GameplayState},
+	stockpile::ResourceStockpile,
+};
+use world_generation::{
+	mapping::map::Map, prelude::GenerationConfig, states::GeneratorState, tile_manager::TileManager,
 };
-use world_generation::{mapping::map::Map, prelude::GenerationConfig, states::GeneratorState};
 
 use crate::{
 	assets::{
-		building_asset::{BuildingAsset, BuildingAssetPlugin},
-		building_database::BuildingDatabase,
+		building_asset::{BuildingAsset, BuildingAssetPlugin, BuildingType},
+		building_database::{BuildingDatabase, BuildingLookup},
+	},
+	build_queue::{
+		BuildQueue, CancelQueueEntry, QueueEntry, QueueEntryRejected, QueueEntryState, cancel_queue_entries,
+		update_build_queue,
 	},
-	build_queue::{BuildQueue, QueueEntry},
 	buildings_map::{BuildingEntry, BuildingMap},
+	construction::{
+		CancelConstruction, ConstructionCompleted, PauseConstruction, UnderConstruction, cancel_construction,
+		cancel_invalid_sites, complete_construction, pause_construction, progress_construction,
+	},
+	demolition::{DemolishBuilding, demolish_input, start_demolition, tick_demolition},
+	drones::{BuilderDrone, assign_builder_drones, spawn_builder_drones},
+	placement::{PlacementRotation, get_rotation_quat, rotate_placement},
 	prelude::Building,
 };
 
@@ -32,6 +42,15 @@ impl Plugin for BuildingPlugin
 	fn build(&self, app: &mut App)
 	{
 		app.insert_resource(BuildQueue::default());
+		app.init_resource::<PlacementRotation>();
+		app.init_resource::<ResourceStockpile>();
+		app.add_message::<DemolishBuilding>();
+		app.add_message::<BuildingRemoved>();
+		app.add_message::<PauseConstruction>();
+		app.add_message::<CancelConstruction>();
+		app.add_message::<ConstructionCompleted>();
+		app.add_message::<CancelQueueEntry>();
+		app.add_message::<QueueEntryRejected>();
 		app.add_plugins(BuildingAssetPlugin);
 
 		app.configure_loading_state(
@@ -39,9 +58,13 @@ impl Plugin for BuildingPlugin
 		);
 
 		app.add_systems(Update, init.run_if(in_state(AssetLoadState::Loading)));
+		//Tiles are registered while the assets are finalized, placement rules refer to them
+		app.add_systems(OnEnter(AssetLoadState::LoadComplete), create_building_lookup);
 		app.add_systems(
 			Update,
-			hq_placement.run_if(in_state(GameplayState::PlaceHQ).and_then(in_state(GeneratorState::Idle))),
+			(rotate_placement, hq_placement)
+				.chain()
+				.run_if(in_state(GameplayState::PlaceHQ).and_then(in_state(GeneratorState::Idle))),
 		);
 		app.add_systems(
 			PreUpdate,
@@ -54,6 +77,25 @@ impl Plugin for BuildingPlugin
 		);
 
 		app.add_systems(PreUpdate, process_build_queue.run_if(in_state(GameplayState::Playing)));
+		app.add_systems(
+			Update,
+			(
+				demolish_input,
+				cancel_queue_entries,
+				cancel_invalid_sites,
+				cancel_construction,
+				pause_construction,
+				assign_builder_drones,
+				progress_construction,
+				complete_construction,
+				spawn_builder_drones,
+				update_build_queue,
+				start_demolition,
+				tick_demolition,
+			)
+				.chain()
+				.run_if(in_state(GameplayState::Playing)),
+		);
 	}
 }
 
@@ -62,23 +104,91 @@ fn prepare_building_map(mut commands: Commands, cfg: Res<GenerationConfig>)
 	commands.insert_resource(BuildingMap::new(cfg.size));
 }
 
-fn regernerate(mut commands: Commands, buildings: Query<Entity, With<Building>>, cfg: Res<GenerationConfig>)
+fn regernerate(
+	mut commands: Commands,
+	buildings: Query<Entity, Or<(With<Building>, With<UnderConstruction>, With<BuilderDrone>)>>,
+	cfg: Res<GenerationConfig>,
+)
 {
 	for e in buildings.iter() {
 		commands.entity(e).despawn();
 	}
 	commands.insert_resource(BuildingMap::new(cfg.size));
+	commands.insert_resource(BuildQueue::default());
+	commands.insert_resource(ResourceStockpile::default());
+}
+
+/// Resolves the building keys once every building is loaded, reports buildings that unlock unknown buildings
+/// or use unknown resources or tiles
+fn create_building_lookup(
+	mut commands: Commands,
+	db: Res<BuildingDatabase>,
+	mut building_assets: ResMut<Assets<BuildingAsset>>,
+	asset_server: Res<AssetServer>,
+	resource_lookup: Option<Res<ResourceLookup>>,
+	tile_manager: Res<TileManager>,
+)
+{
+	for (_, building) in building_assets.iter_mut() {
+		if let Err(errors) = building.placement.resolve_tiles(&tile_manager) {
+			for err in errors {
+				error!("{}: {}", building.name, err);
+			}
+		}
+	}
+	let lookup = match db.create_lookup(&building_assets, &asset_server) {
+		Ok(lookup) => lookup,
+		Err(err) => {
+			error!("{}", err);
+			return;
+		}
+	};
+	for (_, building) in building_assets.iter() {
+		if let BuildingType::Tech(info) = &building.building_type
+			&& let Err(errors) = lookup.get_ids(info.buildings_to_unlock.iter().map(|k| k.as_str()))
+		{
+			for err in errors {
+				error!("{}: {}", building.name, err);
+			}
+		}
+	}
+	match resource_lookup {
+		Some(resource_lookup) => {
+			for (_, building) in building_assets.iter() {
+				let amounts = [
+					&building.cost,
+					&building.consumption,
+					&building.production,
+					&building.starting_resources,
+				];
+				for amount in amounts.into_iter().flatten() {
+					if let Err(err) = resource_lookup.resolve(amount) {
+						error!("{}: {}", building.name, err);
+					}
+				}
+			}
+		}
+		None => error!("Resources are not registered, the resources buildings use cannot be checked"),
+	}
+	info!("Registered {} buildings", lookup.len());
+	commands.insert_resource(lookup);
 }
 
+/// Mesh and material of placement previews and construction sites, the last material marks invalid placements
 #[derive(Resource)]
-struct IndicatorCube(Handle<Mesh>, Handle<StandardMaterial>);
+pub(crate) struct IndicatorCube(
+	pub(crate) Handle<Mesh>,
+	pub(crate) Handle<StandardMaterial>,
+	pub(crate) Handle<StandardMaterial>,
+);
 
 fn init(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>)
 {
 	let cube = Cuboid::from_size(Vec3::splat(1.));
 	let mesh_handle = meshes.add(cube);
 	let mat_handle = materials.add(Color::WHITE);
-	commands.insert_resource(IndicatorCube(mesh_handle, mat_handle));
+	let invalid_handle = materials.add(Color::srgb(1., 0.2, 0.2));
+	commands.insert_resource(IndicatorCube(mesh_handle, mat_handle, invalid_handle));
 }
 
 fn hq_placement(
@@ -87,72 +197,153 @@ fn hq_placement(
 	tile_under_cursor: Res<TileUnderCursor>,
 	map: Res<Map>,
 	indicator: Res<IndicatorCube>,
+	building_map: Res<BuildingMap>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+	rotation: Res<PlacementRotation>,
 	mut build_queue: ResMut<BuildQueue>,
+	mut stockpile: ResMut<ResourceStockpile>,
 	mut next_state: ResMut<NextState<GameplayState>>,
 )
 {
 	if let Some(contact) = tile_under_cursor.0 {
-		let positions = map.hex_select(&contact.tile, 3, true, |pos, h, _| pos.to_world(h));
-		show_indicators(positions, &mut commands, &indicator);
+		let building = building_lookup.get_id("hq");
+		let asset = building
+			.as_ref()
+			.ok()
+			.and_then(|id| building_lookup.get_handle(*id))
+			.and_then(|handle| building_assets.get(handle.id()));
+		let footprint = asset
+			.map(|asset| asset.footprint.get_footprint(&contact.tile, rotation.0).get_coords())
+			.unwrap_or_else(|| vec![contact.tile]);
+		let placement = match asset {
+			Some(asset) => building_map
+				.check_footprint(&footprint)
+				.map_err(|err| err.to_string())
+				.and_then(|_| asset.validate_placement(&footprint, &map)),
+			None => Ok(()),
+		};
+		let positions = footprint
+			.iter()
+			.filter(|coord| map.is_in_bounds(coord))
+			.map(|coord| coord.to_world(map.sample_height(coord)))
+			.collect();
+		show_indicators(positions, placement.is_ok(), &mut commands, &indicator);
 
 		if mouse.just_pressed(MouseButton::Left) {
-			build_queue.queue.push(QueueEntry {
-				building: 0.into(),
-				pos: contact.tile,
-			});
+			let building = match building {
+				Ok(building) => building,
+				Err(err) => {
+					error!("{}", err);
+					return;
+				}
+			};
+			if let Err(reason) = placement {
+				warn!("{}", reason);
+				return;
+			}
+			build_queue.push(building, contact.tile, rotation.0, 0);
+			if let Some(asset) = asset {
+				stockpile.add_all(&asset.starting_resources);
+			}
 
 			next_state.set(GameplayState::Playing);
 		}
 	}
 }
 
-fn show_indicators(positions: Vec<Vec3>, commands: &mut Commands, indicator: &IndicatorCube)
+fn show_indicators(positions: Vec<Vec3>, valid: bool, commands: &mut Commands, indicator: &IndicatorCube)
 {
+	let material = if valid { &indicator.1 } else { &indicator.2 };
 	for p in positions {
 		commands.spawn((
 			Mesh3d(indicator.0.clone()),
-			MeshMaterial3d(indicator.1.clone()),
+			MeshMaterial3d(material.clone()),
 			Transform::from_translation(p),
 			Despawn,
 		));
 	}
 }
 
+/// Starts a construction site for every queued building that has none yet, the site reserves the footprint
 fn process_build_queue(
 	mut queue: ResMut<BuildQueue>,
 	mut commands: Commands,
-	db: Res<BuildingDatabase>,
+	building_lookup: Res<BuildingLookup>,
 	building_assets: Res<Assets<BuildingAsset>>,
-	gltf_assets: Res<Assets<Gltf>>,
-	gltf_meshes: Res<Assets<GltfMesh>>,
-	gltf_nodes: Res<Assets<GltfNode>>,
+	indicator: Res<IndicatorCube>,
 	mut building_map: ResMut<BuildingMap>,
 	heightmap: Res<Map>,
+	mut rejected: MessageWriter<QueueEntryRejected>,
 )
 {
-	for item in &queue.queue {
-		let handle = &db.buildings[item.building.0];
+	let mut rejected_ids = Vec::new();
+	for item in queue.iter_mut().filter(|item| item.site.is_none()) {
+		let Some(handle) = building_lookup.get_handle(item.building) else {
+			let reason = format!("Building {} does not exist", item.building.0);
+			set_blocked(item, reason);
+			continue;
+		};
 		if let Some(building) = building_assets.get(handle.id()) {
-			let h = heightmap.sample_height(&item.pos);
-			println!("Spawning {} at {}", building.name, item.pos);
-			if let Some(gltf) = gltf_assets.get(building.prefab.id()) {
-				let e = building.spawn(
-					item.pos.to_world(h),
-					Quat::IDENTITY,
-					gltf,
-					&mut commands,
-					&gltf_meshes,
-					&gltf_nodes,
-				);
-				if let Some(b) = e {
-					building_map.add_building(BuildingEntry::new(item.pos, b));
-				}
-			} else {
-				warn!("Failed to spawn building");
+			let footprint = building.footprint.get_footprint(&item.pos, item.rotation).get_coords();
+			if let Err(err) = building_map.check_footprint(&footprint) {
+				set_blocked(item, format!("Can't place {} at {}: {}", building.name, item.pos, err));
+				continue;
+			}
+			//The terrain does not fix itself, so invalid entries are dropped instead of retried
+			if let Err(reason) = building.validate_placement(&footprint, &heightmap) {
+				warn!("{}", reason);
+				rejected.write(QueueEntryRejected {
+					id: item.id(),
+					building: item.building,
+					pos: item.pos,
+					reason,
+				});
+				rejected_ids.push(item.id());
+				continue;
+			}
+			let pos = item.pos.to_world(heightmap.sample_height(&item.pos));
+			let rot = get_rotation_quat(item.rotation);
+			info!("Constructing {} at {}", building.name, item.pos);
+			let site = commands
+				.spawn((
+					Transform::from_translation(pos).with_rotation(rot),
+					Visibility::default(),
+					UnderConstruction::new(item.building, item.pos, item.rotation),
+				))
+				.id();
+			//Scaffold cubes over every tile of the footprint, placed relative to the rotated site
+			for coord in &footprint {
+				let offset = coord.to_world(heightmap.sample_height(coord)) - pos;
+				commands.spawn((
+					Mesh3d(indicator.0.clone()),
+					MeshMaterial3d(indicator.1.clone()),
+					Transform::from_translation(rot.inverse() * offset),
+					ChildOf(site),
+				));
+			}
+			if let Err(err) =
+				building_map.add_building(BuildingEntry::new(item.pos, site, item.building).with_footprint(footprint))
+			{
+				warn!("Failed to reserve the footprint of {}: {}", building.name, err);
 			}
+			item.site = Some(site);
+			item.state = QueueEntryState::InProgress;
 		}
 	}
-	queue.queue.clear();
+	for id in rejected_ids {
+		queue.remove(id);
+	}
+}
+
+/// Blocked entries are retried every frame, the reason is only logged when it changes
+fn set_blocked(item: &mut QueueEntry, reason: String)
+{
+	if item.state == QueueEntryState::Blocked(reason.clone()) {
+		return;
+	}
+	warn!("{}", reason);
+	item.state = QueueEntryState::Blocked(reason);
 }
 
 fn update_building_heights(
@@ -162,8 +353,10 @@ fn update_building_heights(
 )
 {
 	for event in tile_updates.read() {
+		//Buildings follow the height of their main tile
 		if let TileModifiedEvent::HeightChanged(coord, new_height) = event
-			&& let Some(building) = building_map.get_building(coord)
+			&& let Some(building) = building_map.get_entry(coord)
+			&& building.is_main
 		{
 			let mut queue = CommandQueue::default();
 			let e = building.entity;
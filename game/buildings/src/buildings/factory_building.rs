@@ -2,5 +2,6 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FactoryBuildingInfo {
-	pub units_to_build: Vec<()>
+	/// Keys of the units
+	pub units_to_build: Vec<String>,
 }
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
-use shared::identifiers::ResourceIdentifier;
+use shared::identifiers::ResourceAmount;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ResourceGatheringBuildingInfo {
-	pub resources_to_gather: Vec<ResourceIdentifier>,
+	pub resources_to_gather: Vec<ResourceAmount>,
 	pub gather_range: usize,
 }
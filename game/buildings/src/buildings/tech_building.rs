@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
-use shared::{building::BuildingIdentifier, StatusEffect};
+use shared::StatusEffect;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TechBuildingInfo {
 	pub effect_range: usize,
-	pub buildings_to_unlock: Vec<BuildingIdentifier>,
+	/// Keys of the buildings
+	pub buildings_to_unlock: Vec<String>,
 	pub buffs: Vec<StatusEffect>,
 }
@@ -1,5 +1,35 @@
+use std::fmt::Display;
+
 use bevy::prelude::*;
 use hex::prelude::*;
+use shared::building::BuildingIdentifier;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildingMapError
+{
+	OutOfBounds(HexCoord),
+	Occupied
+	{
+		coord: HexCoord, entity: Entity
+	},
+}
+
+impl Display for BuildingMapError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self
+		{
+			BuildingMapError::OutOfBounds(coord) => write!(f, "Tile {} is outside of the map", coord),
+			BuildingMapError::Occupied { coord, entity } =>
+			{
+				write!(f, "Tile {} is already covered by building {}", coord, entity)
+			}
+		};
+	}
+}
+
+impl std::error::Error for BuildingMapError {}
 
 #[derive(Resource)]
 pub struct BuildingMap
@@ -40,13 +70,18 @@ impl BuildingMap
 		return self.get_buildings_in_coords(coords);
 	}
 
+	/// Gets every building covering one of the coords, buildings covering several of them are only returned once
 	pub fn get_buildings_in_coords(&self, coords: Vec<HexCoord>) -> Vec<&BuildingEntry>
 	{
-		let mut result = Vec::new();
+		let mut result: Vec<&BuildingEntry> = Vec::new();
 		for coord in &coords
 		{
 			if let Some(buidling) = self.get_building(coord)
 			{
+				if result.iter().any(|b| b.entity == buidling.entity)
+				{
+					continue;
+				}
 				result.push(buidling);
 			}
 		}
@@ -54,16 +89,96 @@ impl BuildingMap
 		return result;
 	}
 
+	/// Gets the building covering a tile, every tile of a footprint returns the entry of the building's main tile
 	pub fn get_building(&self, coord: &HexCoord) -> Option<&BuildingEntry>
 	{
+		let entry = self.get_entry(coord)?;
+		if let Some(main_coord) = &entry.main_coord
+		{
+			return self.get_entry(main_coord);
+		}
+		return Some(entry);
+	}
+
+	/// Iterates the main entry of every building
+	pub fn iter_buildings(&self) -> impl Iterator<Item = &BuildingEntry>
+	{
+		return self.chunks.iter().flat_map(|chunk| &chunk.entries).filter(|entry| entry.is_main);
+	}
+
+	/// Gets the entry stored at a tile, this is a child entry for the footprint tiles next to a building's main tile
+	pub fn get_entry(&self, coord: &HexCoord) -> Option<&BuildingEntry>
+	{
+		if !self.is_in_bounds(coord)
+		{
+			return None;
+		}
 		let chunk = &self.chunks[coord.to_chunk_index(self.size.x as usize)];
 		return chunk.get_building(coord);
 	}
 
-	pub fn add_building(&mut self, entry: BuildingEntry)
+	pub fn is_in_bounds(&self, coord: &HexCoord) -> bool
+	{
+		return coord.is_in_bounds(
+			self.size.y as usize * Chunk::SIZE,
+			self.size.x as usize * Chunk::SIZE,
+		);
+	}
+
+	/// Checks that every tile of a footprint is on the map and not covered by a building
+	pub fn check_footprint(&self, footprint: &[HexCoord]) -> Result<(), BuildingMapError>
+	{
+		for coord in footprint
+		{
+			if !self.is_in_bounds(coord)
+			{
+				return Err(BuildingMapError::OutOfBounds(*coord));
+			}
+			if let Some(other) = self.get_building(coord)
+			{
+				return Err(BuildingMapError::Occupied {
+					coord: *coord,
+					entity: other.entity,
+				});
+			}
+		}
+		return Ok(());
+	}
+
+	/// Adds a building and reserves the rest of its footprint with child entries pointing to it
+	///
+	/// Nothing is added when any tile of the footprint is outside of the map or already covered
+	pub fn add_building(&mut self, entry: BuildingEntry) -> Result<(), BuildingMapError>
 	{
+		self.check_footprint(&entry.footprint)?;
+		for coord in &entry.footprint
+		{
+			if coord == &entry.coord
+			{
+				continue;
+			}
+			let child = BuildingEntry::new_with_parent(*coord, &entry);
+			self.chunks[coord.to_chunk_index(self.size.x as usize)].add_building(child);
+		}
 		let chunk = &mut self.chunks[entry.coord.to_chunk_index(self.size.x as usize)];
 		chunk.add_building(entry);
+		return Ok(());
+	}
+
+	/// Removes the building covering a tile and frees its whole footprint, returns the building's main entry
+	pub fn remove_building(&mut self, coord: &HexCoord) -> Option<BuildingEntry>
+	{
+		let main_coord = self.get_building(coord)?.coord;
+		let width = self.size.x as usize;
+		let main = self.chunks[main_coord.to_chunk_index(width)].remove_building(&main_coord)?;
+		for coord in &main.footprint
+		{
+			if coord != &main.coord
+			{
+				self.chunks[coord.to_chunk_index(width)].remove_building(coord);
+			}
+		}
+		return Some(main);
 	}
 }
 
@@ -94,53 +209,146 @@ impl BuildingChunk
 	{
 		self.entries.push(entry);
 	}
+
+	pub fn remove_building(&mut self, coord: &HexCoord) -> Option<BuildingEntry>
+	{
+		let index = self.entries.iter().position(|b| &b.coord == coord)?;
+		return Some(self.entries.swap_remove(index));
+	}
 }
 
 pub struct BuildingEntry
 {
 	pub coord: HexCoord,
 	pub entity: Entity,
+	pub building: BuildingIdentifier,
 	pub is_main: bool,
 	pub main_entity: Option<Entity>,
+	/// Main tile of the building a child entry belongs to
+	pub main_coord: Option<HexCoord>,
 	pub has_children: bool,
 	pub child_entities: Option<Vec<Entity>>,
+	/// Every tile the building covers, only set on the main entry
+	pub footprint: Vec<HexCoord>,
 }
 
 impl BuildingEntry
 {
-	pub fn new(coord: HexCoord, entity: Entity) -> Self
+	pub fn new(coord: HexCoord, entity: Entity, building: BuildingIdentifier) -> Self
 	{
 		return BuildingEntry {
 			coord,
 			entity,
+			building,
 			child_entities: None,
 			has_children: false,
 			main_entity: None,
+			main_coord: None,
 			is_main: true,
+			footprint: vec![coord],
 		};
 	}
 
-	pub fn new_with_children(coord: HexCoord, entity: Entity, children: Vec<Entity>) -> BuildingEntry
+	pub fn new_with_children(
+		coord: HexCoord,
+		entity: Entity,
+		building: BuildingIdentifier,
+		children: Vec<Entity>,
+	) -> BuildingEntry
 	{
 		return BuildingEntry {
 			coord,
 			entity,
+			building,
 			child_entities: Some(children),
 			has_children: true,
 			main_entity: None,
+			main_coord: None,
 			is_main: true,
+			footprint: vec![coord],
 		};
 	}
 
-	pub fn new_with_parent(coord: HexCoord, entity: Entity, main: Entity) -> BuildingEntry
+	/// Entry of a footprint tile covered by the building of `main`
+	pub fn new_with_parent(coord: HexCoord, main: &BuildingEntry) -> BuildingEntry
 	{
 		return BuildingEntry {
 			coord,
-			entity,
+			entity: main.entity,
+			building: main.building,
 			child_entities: None,
 			has_children: false,
-			main_entity: Some(main),
+			main_entity: Some(main.entity),
+			main_coord: Some(main.coord),
 			is_main: false,
+			footprint: Vec::new(),
 		};
 	}
+
+	/// Sets the tiles the building covers, the main tile is always part of the footprint
+	pub fn with_footprint(mut self, footprint: Vec<HexCoord>) -> Self
+	{
+		self.footprint = footprint;
+		if !self.footprint.contains(&self.coord)
+		{
+			self.footprint.push(self.coord);
+		}
+		return self;
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn footprint_reservation_and_removal()
+	{
+		let mut world = World::new();
+		let (hq, mine) = (world.spawn_empty().id(), world.spawn_empty().id());
+		let mut map = BuildingMap::new(UVec2::splat(2));
+		let origin = HexCoord::from_axial(IVec2::new(5, 5));
+		let footprint: Vec<_> = [IVec2::ZERO, IVec2::X, IVec2::Y]
+			.iter()
+			.map(|p| HexCoord::from_axial(*p + origin.hex.xy()))
+			.collect();
+
+		map.add_building(BuildingEntry::new(origin, hq, 0.into()).with_footprint(footprint.clone())).unwrap();
+		for coord in &footprint
+		{
+			let building = map.get_building(coord).unwrap();
+			assert_eq!(building.entity, hq);
+			assert_eq!(building.coord, origin);
+			assert!(building.is_main);
+		}
+		assert!(!map.get_entry(&footprint[1]).unwrap().is_main);
+		assert_eq!(map.get_buildings_in_range(&origin, 2).len(), 1);
+
+		let overlap = HexCoord::from_axial(origin.hex.xy() + IVec2::new(1, 1));
+		let mine_entry = || BuildingEntry::new(overlap, mine, 1.into()).with_footprint(vec![overlap, footprint[2]]);
+		let result = map.add_building(mine_entry());
+		assert_eq!(
+			result,
+			Err(BuildingMapError::Occupied {
+				coord: footprint[2],
+				entity: hq
+			})
+		);
+		assert!(map.get_building(&overlap).is_none());
+
+		let removed = map.remove_building(&footprint[2]).unwrap();
+		assert_eq!(removed.entity, hq);
+		assert_eq!(removed.footprint, footprint);
+		assert!(footprint.iter().all(|c| map.get_entry(c).is_none()));
+		assert!(map.remove_building(&origin).is_none());
+		map.add_building(mine_entry()).unwrap();
+		assert_eq!(map.get_building(&footprint[2]).unwrap().entity, mine);
+
+		let outside = HexCoord::from_axial(IVec2::new(-1, 0));
+		assert_eq!(
+			map.add_building(BuildingEntry::new(outside, mine, 1.into())),
+			Err(BuildingMapError::OutOfBounds(outside))
+		);
+	}
 }
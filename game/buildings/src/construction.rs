@@ -0,0 +1,413 @@
+use bevy::{
+	gltf::{GltfMesh, GltfNode},
+	prelude::*,
+};
+use hex::prelude::*;
+use shared::{
+	StatusEffect, building::BuildingIdentifier, events::TileModifiedEvent, identifiers::ResourceAmount,
+	stockpile::ResourceStockpile,
+};
+use world_generation::mapping::map::Map;
+
+use crate::{
+	assets::{
+		building_asset::{BuildingAsset, BuildingType},
+		building_database::BuildingLookup,
+	},
+	buildings_map::{BuildingEntry, BuildingMap},
+	demolition::DemolishBuilding,
+	drones::BuilderDrone,
+	placement::get_rotation_quat,
+};
+
+/// A building site, the building's prefab replaces it once the progress reaches 1
+#[derive(Component)]
+pub struct UnderConstruction
+{
+	pub building: BuildingIdentifier,
+	pub coord: HexCoord,
+	pub rotation: i32,
+	/// From 0 to 1
+	pub progress: f32,
+	/// Resources paid so far, in the order of the building's cost
+	pub paid: Vec<u32>,
+	pub paused: bool,
+	/// The resources for the next bit of progress are not in the stockpile
+	pub waiting_for_resources: bool,
+	/// Builder drones working on the site this frame
+	pub builders: u32,
+}
+
+impl UnderConstruction
+{
+	pub fn new(building: BuildingIdentifier, coord: HexCoord, rotation: i32) -> Self
+	{
+		return Self {
+			building,
+			coord,
+			rotation,
+			progress: 0.,
+			paid: Vec::new(),
+			paused: false,
+			waiting_for_resources: false,
+			builders: 0,
+		};
+	}
+}
+
+/// Pauses or resumes the construction site covering a tile
+#[derive(Message)]
+pub struct PauseConstruction
+{
+	pub coord: HexCoord,
+	pub paused: bool,
+}
+
+/// Cancels the construction site covering a tile, everything paid so far is refunded
+#[derive(Message)]
+pub struct CancelConstruction
+{
+	pub coord: HexCoord,
+}
+
+/// Sent when a construction site is finished, the site is replaced by the building's prefab
+#[derive(Message)]
+pub struct ConstructionCompleted
+{
+	pub site: Entity,
+	pub building: BuildingIdentifier,
+	pub coord: HexCoord,
+	pub rotation: i32,
+}
+
+/// Advances every construction site, paying the share of the cost each bit of progress needs as it goes.
+/// Sites need builder drones working on them, they build on their own while the colony has no drones
+pub fn progress_construction(
+	time: Res<Time>,
+	mut sites: Query<(Entity, &mut UnderConstruction)>,
+	drones: Query<(), With<BuilderDrone>>,
+	mut stockpile: ResMut<ResourceStockpile>,
+	mut completed: MessageWriter<ConstructionCompleted>,
+	building_map: Res<BuildingMap>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+)
+{
+	let get_asset = |id: BuildingIdentifier| {
+		return building_lookup
+			.get_handle(id)
+			.and_then(|handle| building_assets.get(handle.id()));
+	};
+	//Only finished tech buildings give buffs
+	let tech_buildings: Vec<_> = building_map
+		.iter_buildings()
+		.filter(|entry| !sites.contains(entry.entity))
+		.filter_map(|entry| match &get_asset(entry.building)?.building_type {
+			BuildingType::Tech(info) => Some((entry.coord, info)),
+			_ => None,
+		})
+		.collect();
+
+	for (entity, mut site) in sites.iter_mut() {
+		if site.paused || site.progress >= 1. || (site.builders == 0 && !drones.is_empty()) {
+			continue;
+		}
+		let Some(asset) = get_asset(site.building) else {
+			continue;
+		};
+		let speed = site.builders.max(1) as f32
+			* get_build_speed(
+				tech_buildings
+					.iter()
+					.filter(|(coord, info)| (coord.distance(&site.coord) / 2) as usize <= info.effect_range)
+					.flat_map(|(_, info)| &info.buffs),
+			);
+		let step = if asset.build_time > 0. {
+			time.delta_secs() * speed / asset.build_time
+		} else {
+			1.
+		};
+		let progress = (site.progress + step).min(1.);
+		if !pay_for_progress(&mut site, &asset.cost, progress, &mut stockpile) {
+			continue;
+		}
+
+		if site.progress >= 1. {
+			completed.write(ConstructionCompleted {
+				site: entity,
+				building: site.building,
+				coord: site.coord,
+				rotation: site.rotation,
+			});
+		}
+	}
+}
+
+/// Replaces finished construction sites with the building's prefab
+pub fn complete_construction(
+	mut commands: Commands,
+	mut completed: MessageReader<ConstructionCompleted>,
+	mut building_map: ResMut<BuildingMap>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+	gltf_assets: Res<Assets<Gltf>>,
+	gltf_meshes: Res<Assets<GltfMesh>>,
+	gltf_nodes: Res<Assets<GltfNode>>,
+	heightmap: Res<Map>,
+)
+{
+	for event in completed.read() {
+		if building_map
+			.get_building(&event.coord)
+			.is_none_or(|entry| entry.entity != event.site)
+		{
+			continue;
+		}
+		let Some(site) = building_map.remove_building(&event.coord) else {
+			continue;
+		};
+		commands.entity(event.site).despawn();
+		let Some(building) = building_lookup
+			.get_handle(event.building)
+			.and_then(|handle| building_assets.get(handle.id()))
+		else {
+			warn!("Building {} does not exist", event.building.0);
+			continue;
+		};
+		let Some(gltf) = gltf_assets.get(building.prefab.id()) else {
+			warn!("Failed to spawn building");
+			continue;
+		};
+		let h = heightmap.sample_height(&event.coord);
+		let entity = building.spawn(
+			event.coord.to_world(h),
+			get_rotation_quat(event.rotation),
+			gltf,
+			&mut commands,
+			&gltf_meshes,
+			&gltf_nodes,
+		);
+		if let Some(entity) = entity
+			&& let Err(err) = building_map
+				.add_building(BuildingEntry::new(event.coord, entity, event.building).with_footprint(site.footprint))
+		{
+			warn!("Failed to reserve the footprint of {}: {}", building.name, err);
+		}
+	}
+}
+
+pub fn pause_construction(
+	mut requests: MessageReader<PauseConstruction>,
+	building_map: Res<BuildingMap>,
+	mut sites: Query<&mut UnderConstruction>,
+)
+{
+	for request in requests.read() {
+		if let Some(entry) = building_map.get_building(&request.coord)
+			&& let Ok(mut site) = sites.get_mut(entry.entity)
+		{
+			site.paused = request.paused;
+		}
+	}
+}
+
+/// Cancels the construction sites that can't be placed on their tiles anymore after a tile type changed
+pub fn cancel_invalid_sites(
+	mut tile_updates: MessageReader<TileModifiedEvent>,
+	mut cancel: MessageWriter<CancelConstruction>,
+	building_map: Res<BuildingMap>,
+	sites: Query<&UnderConstruction>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+	heightmap: Res<Map>,
+)
+{
+	let mut cancelled = Vec::new();
+	for event in tile_updates.read() {
+		let TileModifiedEvent::TypeChanged(coord, _) = event else {
+			continue;
+		};
+		let Some(entry) = building_map.get_building(coord) else {
+			continue;
+		};
+		if cancelled.contains(&entry.coord) {
+			continue;
+		}
+		let Some(asset) = sites
+			.get(entry.entity)
+			.ok()
+			.and_then(|site| building_lookup.get_handle(site.building))
+			.and_then(|handle| building_assets.get(handle.id()))
+		else {
+			continue;
+		};
+		if let Err(reason) = asset.validate_placement(&entry.footprint, &heightmap) {
+			warn!("Cancelled construction: {}", reason);
+			cancelled.push(entry.coord);
+			cancel.write(CancelConstruction { coord: entry.coord });
+		}
+	}
+}
+
+/// Cancels construction sites, demolishing a site cancels it as well
+pub fn cancel_construction(
+	mut commands: Commands,
+	mut requests: MessageReader<CancelConstruction>,
+	mut demolish: MessageReader<DemolishBuilding>,
+	mut building_map: ResMut<BuildingMap>,
+	mut stockpile: ResMut<ResourceStockpile>,
+	sites: Query<&UnderConstruction>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+)
+{
+	let coords: Vec<_> = requests
+		.read()
+		.map(|r| r.coord)
+		.chain(demolish.read().map(|r| r.coord))
+		.collect();
+	for coord in coords {
+		let Some(entry) = building_map.get_building(&coord) else {
+			continue;
+		};
+		let Ok(site) = sites.get(entry.entity) else {
+			continue;
+		};
+		if let Some(asset) = building_lookup
+			.get_handle(site.building)
+			.and_then(|handle| building_assets.get(handle.id()))
+		{
+			let refund: Vec<_> = asset
+				.cost
+				.iter()
+				.zip(&site.paid)
+				.map(|(amount, paid)| ResourceAmount {
+					resource: amount.resource.clone(),
+					qty: *paid,
+				})
+				.collect();
+			stockpile.add_all(&refund);
+		}
+		let entity = entry.entity;
+		building_map.remove_building(&coord);
+		commands.entity(entity).despawn();
+	}
+}
+
+/// Takes what reaching `progress` costs on top of what the site paid so far and advances it,
+/// the site waits for resources when the stockpile is short and nothing is taken
+pub fn pay_for_progress(
+	site: &mut UnderConstruction,
+	cost: &[ResourceAmount],
+	progress: f32,
+	stockpile: &mut ResourceStockpile,
+) -> bool
+{
+	site.paid.resize(cost.len(), 0);
+	let due = get_due(cost, progress);
+	let payment: Vec<_> = cost
+		.iter()
+		.zip(due.iter().zip(&site.paid))
+		.filter(|(_, (due, paid))| due > paid)
+		.map(|(amount, (due, paid))| ResourceAmount {
+			resource: amount.resource.clone(),
+			qty: due - paid,
+		})
+		.collect();
+	site.waiting_for_resources = !stockpile.remove_all(&payment);
+	if site.waiting_for_resources {
+		return false;
+	}
+	site.paid = due;
+	site.progress = progress;
+	return true;
+}
+
+/// How much of every cost has to be paid to reach `progress`, rounded up so a site never gets ahead of its payment
+pub fn get_due(cost: &[ResourceAmount], progress: f32) -> Vec<u32>
+{
+	return cost
+		.iter()
+		.map(|amount| ((amount.qty as f32 * progress).ceil() as u32).min(amount.qty))
+		.collect();
+}
+
+/// The build speed multiplier of a set of buffs, multipliers stack multiplicatively
+pub fn get_build_speed<'a>(buffs: impl IntoIterator<Item = &'a StatusEffect>) -> f32
+{
+	return buffs
+		.into_iter()
+		.filter_map(|buff| match buff {
+			StatusEffect::BuildSpeedMulti(multiplier) => Some(*multiplier),
+			_ => None,
+		})
+		.product();
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn gradual_payment()
+	{
+		let cost = [
+			ResourceAmount {
+				resource: "iron_ore".into(),
+				qty: 10,
+			},
+			ResourceAmount {
+				resource: "coal".into(),
+				qty: 3,
+			},
+		];
+		assert_eq!(get_due(&cost, 0.), [0, 0]);
+		assert_eq!(get_due(&cost, 0.01), [1, 1]);
+		assert_eq!(get_due(&cost, 0.5), [5, 2]);
+		assert_eq!(get_due(&cost, 1.), [10, 3]);
+	}
+
+	#[test]
+	fn costed_site_completes()
+	{
+		let cost = [
+			ResourceAmount {
+				resource: "iron_ore".into(),
+				qty: 10,
+			},
+			ResourceAmount {
+				resource: "coal".into(),
+				qty: 3,
+			},
+		];
+		let mut site = UnderConstruction::new(BuildingIdentifier(0), HexCoord::new(0, 0), 0);
+		let mut stockpile = ResourceStockpile::default();
+		assert!(!pay_for_progress(&mut site, &cost, 0.25, &mut stockpile));
+		assert!(site.waiting_for_resources);
+		assert_eq!(site.progress, 0.);
+
+		//The starting resources of the headquarters
+		stockpile.add_all(&cost);
+		for step in 1..=4 {
+			assert!(pay_for_progress(&mut site, &cost, step as f32 / 4., &mut stockpile));
+		}
+		assert!(!site.waiting_for_resources);
+		assert_eq!(site.progress, 1.);
+		assert_eq!(site.paid, [10, 3]);
+		assert_eq!(stockpile.get("iron_ore"), 0);
+		assert_eq!(stockpile.get("coal"), 0);
+	}
+
+	#[test]
+	fn build_speed()
+	{
+		let buffs = [
+			StatusEffect::BuildSpeedMulti(1.5),
+			StatusEffect::BuildCostMulti(0.5),
+			StatusEffect::BuildSpeedMulti(2.),
+		];
+		assert_eq!(get_build_speed(&buffs), 3.);
+		assert_eq!(get_build_speed(&[]), 1.);
+	}
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+use shared::{
+	events::BuildingRemoved, identifiers::ResourceAmount, resources::TileUnderCursor, stockpile::ResourceStockpile,
+};
+
+use crate::{
+	assets::{building_asset::BuildingAsset, building_database::BuildingLookup},
+	buildings_map::BuildingMap,
+	construction::UnderConstruction,
+};
+
+/// Share of a building's cost that is refunded when it is demolished
+pub const DEMOLITION_REFUND: f32 = 0.5;
+
+/// Asks to demolish the building covering a tile, any tile of its footprint works
+#[derive(Message)]
+pub struct DemolishBuilding
+{
+	pub coord: HexCoord,
+}
+
+/// A building that is being torn down, it is removed when the timer finishes
+#[derive(Component)]
+pub struct Demolishing
+{
+	pub coord: HexCoord,
+	pub timer: Timer,
+}
+
+/// Demolishes the building under the cursor
+pub fn demolish_input(
+	keys: Res<ButtonInput<KeyCode>>,
+	tile_under_cursor: Res<TileUnderCursor>,
+	mut demolish: MessageWriter<DemolishBuilding>,
+)
+{
+	if !keys.just_pressed(KeyCode::Delete) {
+		return;
+	}
+	if let Some(contact) = tile_under_cursor.0 {
+		demolish.write(DemolishBuilding { coord: contact.tile });
+	}
+}
+
+/// Starts the demolition timer of requested buildings, buildings without a demolition time are removed right away
+pub fn start_demolition(
+	mut commands: Commands,
+	mut requests: MessageReader<DemolishBuilding>,
+	mut building_map: ResMut<BuildingMap>,
+	mut stockpile: ResMut<ResourceStockpile>,
+	mut removed: MessageWriter<BuildingRemoved>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+	demolishing: Query<(), Or<(With<Demolishing>, With<UnderConstruction>)>>,
+)
+{
+	for request in requests.read() {
+		let Some(entry) = building_map.get_building(&request.coord) else {
+			continue;
+		};
+		//Construction sites are cancelled instead
+		if demolishing.contains(entry.entity) {
+			continue;
+		}
+		let asset = building_lookup
+			.get_handle(entry.building)
+			.and_then(|handle| building_assets.get(handle.id()));
+		let demolition_time = asset.map_or(0., |asset| asset.demolition_time);
+		if demolition_time > 0. {
+			commands.entity(entry.entity).insert(Demolishing {
+				coord: entry.coord,
+				timer: Timer::from_seconds(demolition_time, TimerMode::Once),
+			});
+			continue;
+		}
+		let coord = entry.coord;
+		remove_building(
+			&coord,
+			&mut commands,
+			&mut building_map,
+			&mut stockpile,
+			&mut removed,
+			asset,
+		);
+	}
+}
+
+pub fn tick_demolition(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut demolishing: Query<&mut Demolishing>,
+	mut building_map: ResMut<BuildingMap>,
+	mut stockpile: ResMut<ResourceStockpile>,
+	mut removed: MessageWriter<BuildingRemoved>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+)
+{
+	for mut demolition in demolishing.iter_mut() {
+		if !demolition.timer.tick(time.delta()).is_finished() {
+			continue;
+		}
+		let asset = building_map
+			.get_building(&demolition.coord)
+			.and_then(|entry| building_lookup.get_handle(entry.building))
+			.and_then(|handle| building_assets.get(handle.id()));
+		remove_building(
+			&demolition.coord,
+			&mut commands,
+			&mut building_map,
+			&mut stockpile,
+			&mut removed,
+			asset,
+		);
+	}
+}
+
+/// Frees the footprint, despawns the building and refunds part of its cost
+fn remove_building(
+	coord: &HexCoord,
+	commands: &mut Commands,
+	building_map: &mut BuildingMap,
+	stockpile: &mut ResourceStockpile,
+	removed: &mut MessageWriter<BuildingRemoved>,
+	asset: Option<&BuildingAsset>,
+)
+{
+	let Some(entry) = building_map.remove_building(coord) else {
+		return;
+	};
+	commands.entity(entry.entity).despawn();
+	if let Some(asset) = asset {
+		stockpile.add_all(&get_refund(&asset.cost));
+	}
+	removed.write(BuildingRemoved {
+		entity: entry.entity,
+		building: entry.building,
+		coord: entry.coord,
+		footprint: entry.footprint,
+	});
+}
+
+/// The part of a cost that is given back, rounded down
+pub fn get_refund(cost: &[ResourceAmount]) -> Vec<ResourceAmount>
+{
+	return cost
+		.iter()
+		.map(|amount| ResourceAmount {
+			resource: amount.resource.clone(),
+			qty: (amount.qty as f32 * DEMOLITION_REFUND) as u32,
+		})
+		.filter(|amount| amount.qty > 0)
+		.collect();
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn refund()
+	{
+		let amount = |resource: &str, qty| ResourceAmount {
+			resource: resource.into(),
+			qty,
+		};
+		let refund = get_refund(&[amount("iron_ore", 10), amount("coal", 3), amount("gold", 1)]);
+		assert_eq!(refund, [amount("iron_ore", 5), amount("coal", 1)]);
+	}
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use hex::prelude::*;
+use units::components::{Path, PathTaskPending, Target, Unit};
+use world_generation::mapping::map::Map;
+
+use crate::{
+	assets::{building_asset::BuildingAsset, building_database::BuildingLookup},
+	build_queue::BuildQueue,
+	building_plugin::IndicatorCube,
+	construction::{ConstructionCompleted, UnderConstruction},
+};
+
+/// Distance in tiles from a site's main tile within which a drone works on it
+pub const BUILDER_RANGE: i32 = 3;
+
+/// A unit that travels to construction sites and works on them
+#[derive(Component, Default)]
+pub struct BuilderDrone
+{
+	pub site: Option<Entity>,
+}
+
+/// Spawns the builder drones finished buildings provide
+pub fn spawn_builder_drones(
+	mut commands: Commands,
+	mut completed: MessageReader<ConstructionCompleted>,
+	building_lookup: Res<BuildingLookup>,
+	building_assets: Res<Assets<BuildingAsset>>,
+	indicator: Res<IndicatorCube>,
+	heightmap: Res<Map>,
+)
+{
+	for event in completed.read() {
+		let Some(building) = building_lookup
+			.get_handle(event.building)
+			.and_then(|handle| building_assets.get(handle.id()))
+		else {
+			continue;
+		};
+		let pos = event.coord.to_world(heightmap.sample_height(&event.coord));
+		for _ in 0..building.builder_drones {
+			commands.spawn((
+				Mesh3d(indicator.0.clone()),
+				MeshMaterial3d(indicator.1.clone()),
+				Transform::from_translation(pos).with_scale(Vec3::splat(0.5)),
+				Unit,
+				BuilderDrone::default(),
+			));
+		}
+	}
+}
+
+/// Sends idle drones to the least staffed site, in queue order, and counts the drones working on every site
+pub fn assign_builder_drones(
+	mut commands: Commands,
+	queue: Res<BuildQueue>,
+	mut drones: Query<(
+		Entity,
+		&mut BuilderDrone,
+		&Transform,
+		Has<Target>,
+		Has<PathTaskPending>,
+		Has<Path>,
+	)>,
+	mut sites: Query<&mut UnderConstruction>,
+)
+{
+	//Release the drones of finished, cancelled and paused sites
+	for (_, mut drone, ..) in drones.iter_mut() {
+		if let Some(site) = drone.site
+			&& !sites.get(site).is_ok_and(|site| !site.paused)
+		{
+			drone.site = None;
+		}
+	}
+	let open_sites: Vec<_> = queue
+		.iter()
+		.filter_map(|entry| entry.site)
+		.filter(|site| sites.get(*site).is_ok_and(|site| !site.paused))
+		.collect();
+	let mut staff: Vec<_> = open_sites
+		.iter()
+		.map(|site| drones.iter().filter(|(_, drone, ..)| drone.site == Some(*site)).count())
+		.collect();
+	for mut site in sites.iter_mut() {
+		site.builders = 0;
+	}
+
+	for (entity, mut drone, transform, has_target, path_pending, has_path) in drones.iter_mut() {
+		if drone.site.is_none()
+			&& let Some(index) = get_least_staffed(&staff)
+		{
+			staff[index] += 1;
+			drone.site = Some(open_sites[index]);
+		}
+		let Some(mut site) = drone.site.and_then(|site| sites.get_mut(site).ok()) else {
+			continue;
+		};
+		if has_target || path_pending || has_path {
+			continue;
+		}
+		let coord = HexCoord::from_world_pos(transform.translation);
+		if coord.distance(&site.coord) / 2 <= BUILDER_RANGE {
+			site.builders += 1;
+		} else {
+			commands.entity(entity).insert(Target(site.coord));
+		}
+	}
+}
+
+/// Index of the site with the fewest drones, the first one wins ties
+fn get_least_staffed(staff: &[usize]) -> Option<usize>
+{
+	return staff
+		.iter()
+		.enumerate()
+		.min_by_key(|(_, count)| **count)
+		.map(|(index, _)| index);
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn least_staffed()
+	{
+		assert_eq!(get_least_staffed(&[]), None);
+		assert_eq!(get_least_staffed(&[0, 0, 0]), Some(0));
+		assert_eq!(get_least_staffed(&[1, 0, 0]), Some(1));
+		assert_eq!(get_least_staffed(&[2, 1, 1, 2]), Some(1));
+	}
+}
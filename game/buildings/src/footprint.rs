@@ -11,12 +11,15 @@ pub struct BuildingFootprint
 
 impl BuildingFootprint
 {
-	pub fn get_footprint(&self, position: &HexCoord) -> CoordsCollection
+	/// The tiles the building covers when placed at `position`, `rotation` is in sixths of a full turn
+	pub fn get_footprint(&self, position: &HexCoord, rotation: i32) -> CoordsCollection
 	{
-		CoordsCollection::from_points(self.footprint.clone()).with_translation(position)
+		CoordsCollection::from_points(self.footprint.clone())
+			.with_rotation(rotation)
+			.with_translation(position)
 	}
 
-	pub fn get_neighbors(&self, position: &HexCoord) -> CoordsCollection
+	pub fn get_neighbors(&self, position: &HexCoord, rotation: i32) -> CoordsCollection
 	{
 		let n_points: Vec<IVec2> = self
 			.footprint
@@ -34,6 +37,8 @@ impl BuildingFootprint
 			}
 			out_points.push(p);
 		}
-		return CoordsCollection::from_points(out_points).with_translation(position);
+		return CoordsCollection::from_points(out_points)
+			.with_rotation(rotation)
+			.with_translation(position);
 	}
 }
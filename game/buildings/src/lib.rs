@@ -2,7 +2,11 @@ pub mod assets;
 pub mod build_queue;
 pub mod building_plugin;
 pub mod buildings_map;
+pub mod construction;
+pub mod demolition;
+pub mod drones;
 pub mod footprint;
+pub mod placement;
 pub mod prelude;
 mod buildings;
 pub use building_plugin::*;
@@ -0,0 +1,64 @@
+use std::f32::consts::FRAC_PI_3;
+
+use bevy::prelude::*;
+
+/// Rotation of the building being placed, in sixths of a full turn
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct PlacementRotation(pub i32);
+
+impl PlacementRotation
+{
+	pub fn rotate(&mut self, steps: i32)
+	{
+		self.0 = (self.0 + steps).rem_euclid(6);
+	}
+}
+
+/// The transform rotation matching a footprint rotated by `rotation` sixths with `HexCoord::rotate_around`
+pub fn get_rotation_quat(rotation: i32) -> Quat
+{
+	return Quat::from_rotation_y(rotation as f32 * FRAC_PI_3);
+}
+
+/// Q and E rotate the building being placed, the camera does not turn with them while placing
+pub fn rotate_placement(keys: Res<ButtonInput<KeyCode>>, mut rotation: ResMut<PlacementRotation>)
+{
+	if keys.just_pressed(KeyCode::KeyE) {
+		rotation.rotate(1);
+	} else if keys.just_pressed(KeyCode::KeyQ) {
+		rotation.rotate(-1);
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use hex::prelude::*;
+
+	use super::*;
+
+	#[test]
+	fn footprint_and_transform_match()
+	{
+		let center = HexCoord::from_offset(IVec2::new(10, 10));
+		let origin = center.to_world(0.);
+		for offset in [IVec2::new(2, -1), IVec2::new(1, 0), IVec2::new(0, 1)] {
+			let tile = HexCoord::from_axial(center.hex.xy() + offset);
+			for rotation in -6..=6 {
+				let rotated = tile.rotate_around(&center, rotation).to_world(0.) - origin;
+				let transformed = get_rotation_quat(rotation) * (tile.to_world(0.) - origin);
+				assert!(rotated.distance(transformed) < 0.001, "rotation {}", rotation);
+			}
+		}
+	}
+
+	#[test]
+	fn wrap_around()
+	{
+		let mut rotation = PlacementRotation::default();
+		rotation.rotate(-1);
+		assert_eq!(rotation.0, 5);
+		rotation.rotate(3);
+		assert_eq!(rotation.0, 2);
+	}
+}
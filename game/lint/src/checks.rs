@@ -0,0 +1,421 @@
+use std::{
+	collections::{HashMap, HashSet},
+	path::Path,
+};
+
+use bevy::math::IVec2;
+use buildings::{
+	assets::building_asset::{BuildingAsset, BuildingType},
+	footprint::BuildingFootprint,
+};
+use shared::{
+	content_registry::{ContentKind, get_key_from_path},
+	identifiers::ResourceAmount,
+};
+use world_generation::tile_mapper::TileMapperAsset;
+
+use crate::{
+	content::{Content, get_pack, strip_label},
+	report::LintIssue,
+};
+
+/// Cross checks the references between all content files
+pub fn run_checks(content: &Content) -> Vec<LintIssue>
+{
+	let mut issues = Vec::new();
+	check_resources(content, &mut issues);
+	check_buildings(content, &mut issues);
+	check_units(content, &mut issues);
+	check_biomes(content, &mut issues);
+	check_mappers(content, &mut issues);
+	check_tiles(content, &mut issues);
+	return issues;
+}
+
+fn check_resources(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	let keys = content
+		.resources
+		.iter()
+		.map(|f| (f.asset.identifier.clone(), f.path.as_path()));
+	check_unique_keys("resource", keys, issues);
+}
+
+fn check_buildings(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	let resources: HashSet<_> = content.resources.iter().map(|f| f.asset.identifier.clone()).collect();
+	let buildings: Vec<_> = content
+		.buildings
+		.iter()
+		.map(|f| (get_key(&f.asset.identifier, &f.path), f.path.as_path()))
+		.collect();
+	let units: HashSet<_> = content
+		.units
+		.iter()
+		.map(|f| get_key(&f.asset.identifier, &f.path))
+		.collect();
+	check_unique_keys("building", buildings.iter().cloned(), issues);
+	let buildings: HashSet<_> = buildings.into_iter().map(|(key, _)| key).collect();
+
+	for file in &content.buildings {
+		let building = &file.asset;
+		check_resource_keys(&file.path, "cost", &building.cost, &resources, issues);
+		check_resource_keys(&file.path, "consumption", &building.consumption, &resources, issues);
+		check_resource_keys(&file.path, "production", &building.production, &resources, issues);
+		match &building.building_type {
+			BuildingType::Gathering(info) => check_resource_keys(
+				&file.path,
+				"resources_to_gather",
+				&info.resources_to_gather,
+				&resources,
+				issues,
+			),
+			BuildingType::Tech(info) => check_keys(
+				&file.path,
+				"buildings_to_unlock",
+				&info.buildings_to_unlock,
+				&buildings,
+				issues,
+			),
+			BuildingType::FactoryBuildingInfo(info) => {
+				check_keys(&file.path, "units_to_build", &info.units_to_build, &units, issues)
+			}
+			_ => {}
+		}
+
+		check_footprint(&file.path, &building.footprint, issues);
+
+		if check_reference(content, &file.path, "prefab_path", &building.prefab_path, None, issues) {
+			check_prefab_nodes(content, &file.path, building, issues);
+		}
+	}
+}
+
+fn check_units(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	let keys = content
+		.units
+		.iter()
+		.map(|f| (get_key(&f.asset.identifier, &f.path), f.path.as_path()));
+	check_unique_keys("unit", keys, issues);
+	for file in &content.units {
+		check_reference(
+			content,
+			&file.path,
+			"prefab_path",
+			&file.asset.prefab_path,
+			None,
+			issues,
+		);
+	}
+}
+
+fn check_biomes(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	let names: HashSet<_> = content.biomes.iter().map(|b| b.asset.name.as_str()).collect();
+	for file in &content.biomes {
+		let biome = &file.asset;
+		check_reference(
+			content,
+			&file.path,
+			"tile_mapper_path",
+			&biome.tile_mapper_path,
+			Some(ContentKind::Mapper),
+			issues,
+		);
+		let neighbors = biome.allowed_neighbors.iter().flatten();
+		for neighbor in neighbors.chain(biome.transitions.iter().map(|t| &t.neighbor)) {
+			if !names.contains(neighbor.as_str()) {
+				issues.push(LintIssue::error(
+					&file.path,
+					format!("Neighbor biome '{}' does not exist", neighbor),
+				));
+			}
+		}
+	}
+}
+
+fn check_mappers(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	for file in &content.mappers {
+		for tile in &file.asset.tiles_path {
+			check_reference(content, &file.path, "tiles_path", tile, Some(ContentKind::Tile), issues);
+		}
+		check_thresholds(&file.path, &file.asset, issues);
+	}
+}
+
+fn check_tiles(content: &Content, issues: &mut Vec<LintIssue>)
+{
+	let keys = content
+		.tiles
+		.iter()
+		.map(|f| (get_key(&f.asset.identifier, &f.path), f.path.as_path()));
+	check_unique_keys("tile", keys, issues);
+	for file in &content.tiles {
+		let tile = &file.asset;
+		for (field, texture) in [("texture", &tile.texture), ("side_texture", &tile.side_texture)] {
+			if !texture.is_empty() {
+				check_reference(content, &file.path, field, texture, None, issues);
+			}
+		}
+	}
+}
+
+/// Keys of content that does not set one fall back to the file name, the same as the game does
+fn get_key(identifier: &str, path: &Path) -> String
+{
+	if identifier.is_empty() {
+		return get_key_from_path(path).unwrap_or_default();
+	}
+	return identifier.to_string();
+}
+
+/// Keys only have to be unique within a content pack, packs override content of the game and earlier packs
+fn check_unique_keys<'a>(kind: &str, keys: impl Iterator<Item = (String, &'a Path)>, issues: &mut Vec<LintIssue>)
+{
+	let mut used = HashMap::new();
+	for (key, path) in keys {
+		if let Some(other) = used.insert((get_pack(path), key.clone()), path) {
+			issues.push(LintIssue::error(
+				path,
+				format!("The {} key '{}' is already used by {}", kind, key, other.display()),
+			));
+		}
+	}
+}
+
+pub fn check_keys(path: &Path, field: &str, keys: &[String], known: &HashSet<String>, issues: &mut Vec<LintIssue>)
+{
+	for key in keys {
+		if !known.contains(key) {
+			issues.push(LintIssue::error(
+				path,
+				format!("{} uses '{}' which does not exist", field, key),
+			));
+		}
+	}
+}
+
+pub fn check_resource_keys(
+	path: &Path,
+	field: &str,
+	amounts: &[ResourceAmount],
+	resources: &HashSet<String>,
+	issues: &mut Vec<LintIssue>,
+)
+{
+	for amount in amounts {
+		if !resources.contains(&amount.resource) {
+			issues.push(LintIssue::error(
+				path,
+				format!("{} uses resource '{}' which does not exist", field, amount.resource),
+			));
+		}
+		if amount.qty == 0 {
+			issues.push(LintIssue::warning(
+				path,
+				format!("{} uses resource '{}' with a quantity of 0", field, amount.resource),
+			));
+		}
+	}
+}
+
+pub fn check_footprint(path: &Path, footprint: &BuildingFootprint, issues: &mut Vec<LintIssue>)
+{
+	if footprint.footprint.is_empty() {
+		issues.push(LintIssue::error(path, "Footprint is empty"));
+	} else if !footprint.footprint.contains(&IVec2::ZERO) {
+		issues.push(LintIssue::error(path, "Footprint does not contain the origin"));
+	}
+}
+
+/// Thresholds have to be sorted, every tile past the thresholds has to be used by a rule
+pub fn check_thresholds(path: &Path, mapper: &TileMapperAsset, issues: &mut Vec<LintIssue>)
+{
+	if !mapper.thresholds.is_sorted_by(|a, b| a <= b) {
+		issues.push(LintIssue::error(path, "Thresholds are not sorted"));
+	}
+	let tile_count = mapper.tiles_path.len();
+	if mapper.thresholds.len() > tile_count {
+		issues.push(LintIssue::error(
+			path,
+			format!("{} thresholds but only {} tiles", mapper.thresholds.len(), tile_count),
+		));
+	}
+	//Heights above the last threshold use the last tile
+	for index in (mapper.thresholds.len() + 1)..tile_count {
+		if !mapper.rules.iter().any(|r| r.tile == index) {
+			issues.push(LintIssue::warning(
+				path,
+				format!(
+					"Tile {} has no threshold and is not used by any rule",
+					mapper.tiles_path[index]
+				),
+			));
+		}
+	}
+}
+
+/// Checks that a path field points to a file, and to a file of `kind` when set. Returns whether the file exists.
+fn check_reference(
+	content: &Content,
+	path: &Path,
+	field: &str,
+	reference: &str,
+	kind: Option<ContentKind>,
+	issues: &mut Vec<LintIssue>,
+) -> bool
+{
+	if reference.is_empty() {
+		issues.push(LintIssue::error(path, format!("{} is empty", field)));
+		return false;
+	}
+	if !content.has_file(reference) {
+		issues.push(LintIssue::error(
+			path,
+			format!("{} points to {} which does not exist", field, reference),
+		));
+		return false;
+	}
+	if let Some(kind) = kind
+		&& ContentKind::from_path(Path::new(strip_label(reference))) != Some(kind)
+	{
+		issues.push(LintIssue::error(
+			path,
+			format!("{} points to {} which is not a {:?} file", field, reference, kind),
+		));
+	}
+	return true;
+}
+
+/// The base mesh and every component path have to name nodes of the glTF prefab
+fn check_prefab_nodes(content: &Content, path: &Path, building: &BuildingAsset, issues: &mut Vec<LintIssue>)
+{
+	let gltf = match gltf::Gltf::open(content.get_absolute_path(&building.prefab_path)) {
+		Ok(gltf) => gltf,
+		Err(err) => {
+			issues.push(LintIssue::error(
+				path,
+				format!("Failed to read prefab {}: {}", building.prefab_path, err),
+			));
+			return;
+		}
+	};
+	let nodes: HashSet<_> = gltf.nodes().filter_map(|n| n.name()).collect();
+	if !nodes.contains(building.base_mesh_path.as_str()) {
+		issues.push(LintIssue::error(
+			path,
+			format!(
+				"Base mesh '{}' is not a node of {}",
+				building.base_mesh_path, building.prefab_path
+			),
+		));
+	}
+	for component in building.components.iter().flatten() {
+		for node in component.path.split('/').filter(|n| !n.is_empty()) {
+			if !nodes.contains(node) {
+				issues.push(LintIssue::error(
+					path,
+					format!(
+						"Component path '{}' uses node '{}' which is not in {}",
+						component.path, node, building.prefab_path
+					),
+				));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use bevy::prelude::*;
+	use world_generation::tile_mapper::TileMapperRule;
+
+	use super::*;
+	use crate::report::Severity;
+
+	#[test]
+	fn resource_keys()
+	{
+		let mut issues = Vec::new();
+		let amount = |resource: &str, qty| ResourceAmount {
+			resource: resource.into(),
+			qty,
+		};
+		let amounts = [amount("iron_ore", 5), amount("gold", 1), amount("coal", 0)];
+		let resources = HashSet::from(["iron_ore".to_string(), "coal".to_string()]);
+		check_resource_keys(Path::new("mine.building"), "cost", &amounts, &resources, &mut issues);
+		assert_eq!(issues.len(), 2);
+		assert_eq!(issues[0].severity, Severity::Error);
+		assert_eq!(issues[1].severity, Severity::Warning);
+	}
+
+	#[test]
+	fn pack_overrides()
+	{
+		let mut issues = Vec::new();
+		let keys = [
+			("mine", "buildings/mine.building"),
+			("mine", "packs/mining/buildings/mine.building"),
+			("mine", "packs/mining/buildings/deep_mine.building"),
+		];
+		check_unique_keys(
+			"building",
+			keys.iter().map(|(key, path)| (key.to_string(), Path::new(*path))),
+			&mut issues,
+		);
+		assert_eq!(issues.len(), 1);
+		assert_eq!(issues[0].path, Path::new("packs/mining/buildings/deep_mine.building"));
+	}
+
+	#[test]
+	fn footprint()
+	{
+		let path = Path::new("mine.building");
+		let mut issues = Vec::new();
+		let mut footprint = BuildingFootprint {
+			footprint: vec![IVec2::ZERO, IVec2::X],
+		};
+		check_footprint(path, &footprint, &mut issues);
+		assert!(issues.is_empty());
+
+		footprint.footprint.remove(0);
+		check_footprint(path, &footprint, &mut issues);
+		footprint.footprint.clear();
+		check_footprint(path, &footprint, &mut issues);
+		assert_eq!(issues.len(), 2);
+	}
+
+	#[test]
+	fn thresholds()
+	{
+		let path = Path::new("grass.mapper");
+		let mut mapper = TileMapperAsset {
+			tiles: Vec::new(),
+			tiles_path: vec!["sand.tile".into(), "grass.tile".into(), "rock.tile".into()],
+			thresholds: vec![5., 20.],
+			rules: Vec::new(),
+			threshold_noise: 0.,
+		};
+		let mut issues = Vec::new();
+		check_thresholds(path, &mapper, &mut issues);
+		assert!(issues.is_empty());
+
+		mapper.thresholds = vec![20., 5.];
+		check_thresholds(path, &mapper, &mut issues);
+		assert_eq!(issues.len(), 1);
+
+		issues.clear();
+		mapper.thresholds = vec![5.];
+		check_thresholds(path, &mapper, &mut issues);
+		assert_eq!(issues.len(), 1);
+		assert_eq!(issues[0].severity, Severity::Warning);
+
+		issues.clear();
+		mapper.rules.push(TileMapperRule { tile: 2, ..default() });
+		check_thresholds(path, &mapper, &mut issues);
+		assert!(issues.is_empty());
+	}
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use asset_loader::{error::ValidateAsset, format::AssetFormat};
+use buildings::assets::building_asset::BuildingAsset;
+use resources::resource_asset::ResourceAsset;
+use serde::de::DeserializeOwned;
+use shared::{
+	content_pack::{EXTRACTED_FOLDER, PACK_FOLDER},
+	content_registry::ContentKind,
+};
+use units::assets::unit_asset::UnitAsset;
+use world_generation::{biome_asset::BiomeAsset, tile_manager::TileAsset, tile_mapper::TileMapperAsset};
+
+use crate::report::LintIssue;
+
+/// A content file that was read successfully
+pub struct ContentFile<T>
+{
+	/// Path relative to the asset folder, the same path other assets use to refer to it
+	pub path: PathBuf,
+	pub asset: T,
+}
+
+/// Every content file of an asset folder, read with the same types and formats the game loads them with
+#[derive(Default)]
+pub struct Content
+{
+	pub root: PathBuf,
+	pub buildings: Vec<ContentFile<BuildingAsset>>,
+	pub units: Vec<ContentFile<UnitAsset>>,
+	pub resources: Vec<ContentFile<ResourceAsset>>,
+	pub biomes: Vec<ContentFile<BiomeAsset>>,
+	pub mappers: Vec<ContentFile<TileMapperAsset>>,
+	pub tiles: Vec<ContentFile<TileAsset>>,
+}
+
+impl Content
+{
+	/// Reads every content file below `root`, files that fail to read or validate are reported and skipped
+	pub fn load(root: &Path) -> (Self, Vec<LintIssue>)
+	{
+		let mut content = Content {
+			root: root.to_path_buf(),
+			..Default::default()
+		};
+		let mut issues = Vec::new();
+		let mut files = Vec::new();
+		if let Err(err) = collect_files(root, &mut files) {
+			issues.push(LintIssue::error(
+				root,
+				format!("Failed to read the asset folder: {}", err),
+			));
+		}
+		files.sort();
+
+		for file in files {
+			let Some(kind) = ContentKind::from_path(&file) else {
+				continue;
+			};
+			let path = file.strip_prefix(root).unwrap_or(&file).to_path_buf();
+			if let Err(issue) = content.add_file(kind, path) {
+				issues.push(issue);
+			}
+		}
+		return (content, issues);
+	}
+
+	fn add_file(&mut self, kind: ContentKind, path: PathBuf) -> Result<(), LintIssue>
+	{
+		match kind {
+			ContentKind::Building => self.buildings.push(read_file(&self.root, path)?),
+			ContentKind::Unit => self.units.push(read_file(&self.root, path)?),
+			ContentKind::Resource => self.resources.push(read_file(&self.root, path)?),
+			ContentKind::Biome => self.biomes.push(read_file(&self.root, path)?),
+			ContentKind::Mapper => self.mappers.push(read_file(&self.root, path)?),
+			ContentKind::Tile => self.tiles.push(read_file(&self.root, path)?),
+		}
+		return Ok(());
+	}
+
+	pub fn get_file_count(&self) -> usize
+	{
+		return self.buildings.len()
+			+ self.units.len()
+			+ self.resources.len()
+			+ self.biomes.len()
+			+ self.mappers.len()
+			+ self.tiles.len();
+	}
+
+	/// Whether an asset path, relative to the asset folder, points to a file
+	pub fn has_file(&self, path: &str) -> bool
+	{
+		return self.root.join(strip_label(path)).is_file();
+	}
+
+	pub fn get_absolute_path(&self, path: &str) -> PathBuf
+	{
+		return self.root.join(strip_label(path));
+	}
+}
+
+/// The content pack a file belongs to, `None` for content of the game itself
+pub fn get_pack(path: &Path) -> Option<&str>
+{
+	let mut components = path.components().map(|c| c.as_os_str().to_str());
+	if components.next()? != Some(PACK_FOLDER) {
+		return None;
+	}
+	return components.next()?;
+}
+
+/// Removes the `#Label` of a labeled asset path
+pub fn strip_label(path: &str) -> &str
+{
+	return path.split_once('#').map_or(path, |(path, _)| path);
+}
+
+fn read_file<T: DeserializeOwned + ValidateAsset>(root: &Path, path: PathBuf) -> Result<ContentFile<T>, LintIssue>
+{
+	let bytes = std::fs::read(root.join(&path)).map_err(|err| LintIssue::error(&path, err.to_string()))?;
+	let asset: T = AssetFormat::from_path(&path)
+		.deserialize(&path.to_string_lossy(), &bytes)
+		.map_err(|err| LintIssue::error(&path, err.to_string()))?;
+	asset.validate().map_err(|message| LintIssue::error(&path, message))?;
+	return Ok(ContentFile { path, asset });
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()>
+{
+	for entry in std::fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			//Extracted zip packs are copies of the archives
+			if path.file_name().is_some_and(|name| name == EXTRACTED_FOLDER) {
+				continue;
+			}
+			collect_files(&path, files)?;
+		} else {
+			files.push(path);
+		}
+	}
+	return Ok(());
+}
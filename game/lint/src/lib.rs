@@ -0,0 +1,3 @@
+pub mod checks;
+pub mod content;
+pub mod report;
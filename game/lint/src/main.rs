@@ -0,0 +1,35 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use phos_lint::{checks::run_checks, content::Content, report::Severity};
+
+/// Checks every content file of an asset folder, `phos-lint [asset folder]`
+fn main() -> ExitCode
+{
+	let root = std::env::args()
+		.nth(1)
+		.map(PathBuf::from)
+		.unwrap_or_else(|| PathBuf::from("game/main/assets"));
+	if !root.is_dir() {
+		eprintln!("Asset folder {} does not exist", root.display());
+		return ExitCode::FAILURE;
+	}
+
+	let (content, mut issues) = Content::load(&root);
+	issues.extend(run_checks(&content));
+	issues.sort_by(|a, b| a.path.cmp(&b.path));
+	for issue in &issues {
+		println!("{}", issue);
+	}
+
+	let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+	println!(
+		"Checked {} files: {} errors, {} warnings",
+		content.get_file_count(),
+		errors,
+		issues.len() - errors
+	);
+	if errors > 0 {
+		return ExitCode::FAILURE;
+	}
+	return ExitCode::SUCCESS;
+}
@@ -0,0 +1,56 @@
+use std::{
+	fmt::Display,
+	path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity
+{
+	/// Content that works but is most likely a mistake
+	Warning,
+	/// Content that fails to load or breaks the game
+	Error,
+}
+
+/// A problem found in a content file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue
+{
+	pub severity: Severity,
+	/// Path of the file relative to the asset folder
+	pub path: PathBuf,
+	pub message: String,
+}
+
+impl LintIssue
+{
+	pub fn error(path: &Path, message: impl Into<String>) -> Self
+	{
+		return Self {
+			severity: Severity::Error,
+			path: path.to_path_buf(),
+			message: message.into(),
+		};
+	}
+
+	pub fn warning(path: &Path, message: impl Into<String>) -> Self
+	{
+		return Self {
+			severity: Severity::Warning,
+			path: path.to_path_buf(),
+			message: message.into(),
+		};
+	}
+}
+
+impl Display for LintIssue
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		let severity = match self.severity {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		};
+		return write!(f, "{}: {}: {}", severity, self.path.display(), self.message);
+	}
+}
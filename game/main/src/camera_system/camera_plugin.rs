@@ -6,6 +6,7 @@ use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, CursorOptions, PrimaryWindow};
 use hex::prelude::*;
 use shared::sets::GameplaySystems;
+use shared::states::GameplayState;
 use shared::tags::MainCamera;
 use world_generation::prelude::Map;
 use world_generation::states::GeneratorState;
@@ -74,6 +75,7 @@ fn orbit_camera_upate(
 	key: Res<ButtonInput<KeyCode>>,
 	time: Res<Time>,
 	map: Res<Map>,
+	gameplay_state: Res<State<GameplayState>>,
 	#[cfg(debug_assertions)] mut gizmos: Gizmos,
 )
 {
@@ -103,10 +105,12 @@ fn orbit_camera_upate(
 		cursor_options.grab_mode = CursorGrabMode::None;
 		cursor_options.visible = true;
 	}
-	if key.pressed(KeyCode::KeyE) {
+	//Q and E rotate the building being placed instead
+	let placing = *gameplay_state.get() == GameplayState::PlaceHQ;
+	if !placing && key.pressed(KeyCode::KeyE) {
 		let rot = Quat::from_axis_angle(Vec3::Y, f32::to_radians(config.speed) * time.delta_secs());
 		orbit.forward = rot * orbit.forward;
-	} else if key.pressed(KeyCode::KeyQ) {
+	} else if !placing && key.pressed(KeyCode::KeyQ) {
 		let rot = Quat::from_axis_angle(Vec3::Y, f32::to_radians(-config.speed) * time.delta_secs());
 		orbit.forward = rot * orbit.forward;
 	}
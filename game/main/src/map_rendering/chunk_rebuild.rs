@@ -1,16 +1,21 @@
 use bevy::ecs::world::CommandQueue;
+use bevy::platform::collections::HashSet;
 use bevy::prelude::*;
 use bevy::tasks::*;
+use shared::events::ChangeTileType;
 use shared::events::ChunkModifiedEvent;
+use shared::events::SeaLevelChangedEvent;
 use shared::events::TileModifiedEvent;
 use shared::states::AssetLoadState;
 use world_generation::prelude::Map;
 use world_generation::states::GeneratorState;
+use world_generation::tile_manager::{TileAsset, TileManager};
 
 use crate::map_rendering::prefabs::ChunkPrefab;
 use crate::prelude::PhosAssets;
 use crate::prelude::RebuildChunk;
 use crate::prelude::WaterMesh;
+use crate::prelude::WaterSurface;
 use crate::prelude::{PhosChunk, PhosChunkRegistry};
 use crate::utils::chunk_utils::prepare_chunk_mesh_with_collider;
 
@@ -23,7 +28,14 @@ impl Plugin for ChunkRebuildPlugin
 		app.init_resource::<PhosChunkRegistry>();
 		app.add_message::<ChunkModifiedEvent>();
 		app.add_message::<TileModifiedEvent>();
-		app.add_systems(PreUpdate, chunk_rebuilder.run_if(in_state(GeneratorState::Idle)));
+		app.add_message::<ChangeTileType>();
+		app.add_message::<SeaLevelChangedEvent>();
+		app.add_systems(
+			PreUpdate,
+			(apply_tile_type_changes, rebuild_retyped_chunks, chunk_rebuilder)
+				.chain()
+				.run_if(in_state(GeneratorState::Idle)),
+		);
 		app.add_systems(
 			PostUpdate,
 			collider_task_resolver.run_if(in_state(AssetLoadState::LoadComplete)),
@@ -31,10 +43,53 @@ impl Plugin for ChunkRebuildPlugin
 	}
 }
 
+/// Applies requested tile type changes to the map and sends [`TileModifiedEvent::TypeChanged`] for them
+fn apply_tile_type_changes(
+	mut requests: MessageReader<ChangeTileType>,
+	mut tile_modified: MessageWriter<TileModifiedEvent>,
+	mut heightmap: ResMut<Map>,
+	tile_manager: Res<TileManager>,
+	tile_assets: Res<Assets<TileAsset>>,
+)
+{
+	for request in requests.read() {
+		if !heightmap.is_in_bounds(&request.coord) || heightmap.get_tile_type(&request.coord) == request.tile_type {
+			continue;
+		}
+		let Some(tile) = tile_manager.get_tile(request.tile_type, &tile_assets) else {
+			warn!("Tile type {} does not exist", request.tile_type);
+			continue;
+		};
+		heightmap.set_tile_type(&request.coord, tile);
+		tile_modified.write(TileModifiedEvent::TypeChanged(request.coord, request.tile_type));
+	}
+}
+
+/// Rebuilds every chunk with tiles that changed type since the last frame, once per chunk
+fn rebuild_retyped_chunks(
+	mut commands: Commands,
+	mut tile_updates: MessageReader<TileModifiedEvent>,
+	mut chunk_modified: MessageWriter<ChunkModifiedEvent>,
+	heightmap: Res<Map>,
+	chunks: Res<PhosChunkRegistry>,
+)
+{
+	let mut chunk_set = HashSet::new();
+	for event in tile_updates.read() {
+		if let TileModifiedEvent::TypeChanged(coord, _) = event {
+			chunk_set.insert(coord.to_chunk_index(heightmap.width));
+		}
+	}
+	for index in chunk_set {
+		chunk_modified.write(ChunkModifiedEvent { index });
+		commands.entity(chunks.chunks[index]).insert(RebuildChunk);
+	}
+}
+
 fn chunk_rebuilder(
 	mut commands: Commands,
 	chunk_query: Query<(Entity, &PhosChunk), (With<RebuildChunk>, Without<ChunkRebuildTask>)>,
-	heightmap: Res<Map>,
+	mut heightmap: ResMut<Map>,
 )
 {
 	let pool = AsyncComputeTaskPool::get();
@@ -46,6 +101,8 @@ fn chunk_rebuilder(
 		info!("Rebuilding Chunk");
 		let chunk_index = idx.index;
 		let chunk_data = heightmap.get_chunk_mesh_data(chunk_index);
+		heightmap.update_tile_classes(&[chunk_index]);
+		let sealevel = chunk_data.sealevel;
 
 		let task = pool.spawn(async move {
 			#[cfg(feature = "tracing")]
@@ -69,7 +126,7 @@ fn chunk_rebuilder(
 
 		commands
 			.entity(chunk_entity)
-			.insert(ChunkRebuildTask { task })
+			.insert(ChunkRebuildTask { task, sealevel })
 			.remove::<RebuildChunk>();
 	}
 }
@@ -79,11 +136,13 @@ fn collider_task_resolver(
 	mut commands: Commands,
 	mut meshes: ResMut<Assets<Mesh>>,
 	atlas: Res<PhosAssets>,
+	heightmap: Res<Map>,
 )
 {
 	for (entity, mut task, mesh_handle, phos_chunk, water_mesh_handle) in &mut chunks {
 		if let Some((mut c, chunk_mesh, water_mesh)) = futures::check_ready(&mut task.task) {
 			commands.append(&mut c);
+			let water_offset = Vec3::Y * (heightmap.sealevel - task.sealevel);
 			meshes
 				.insert(mesh_handle.id(), chunk_mesh)
 				.expect("Failed to update chunk mesh");
@@ -92,6 +151,10 @@ fn collider_task_resolver(
 					meshes
 						.insert(water_mesh_handle.0, water_mesh)
 						.expect("Failed to update chink water mesh");
+					commands.entity(water_mesh_handle.1).insert((
+						Transform::from_translation(water_offset),
+						WaterSurface { level: task.sealevel },
+					));
 				} else {
 					info!("Despawn water {}", phos_chunk.index);
 					meshes.remove(water_mesh_handle.0);
@@ -104,10 +167,11 @@ fn collider_task_resolver(
 					let handle = meshes.add(water_mesh);
 					let water_entity = commands
 						.spawn(ChunkPrefab::water(
-							Vec3::ZERO,
+							water_offset,
 							handle.clone(),
 							atlas.water_material.clone(),
 							phos_chunk.index,
+							task.sealevel,
 						))
 						.id();
 					commands
@@ -124,4 +188,82 @@ fn collider_task_resolver(
 struct ChunkRebuildTask
 {
 	pub task: Task<(CommandQueue, Mesh, Option<Mesh>)>,
+	/// Sea level the water mesh is built at
+	pub sealevel: f32,
+}
+
+#[cfg(test)]
+mod tests
+{
+	use hex::prelude::*;
+	use units::{nav_data::NavData, units_plugin::update_navdata};
+
+	use super::*;
+
+	fn create_tile(id: usize, identifier: &str, move_cost: f32) -> TileAsset
+	{
+		return TileAsset {
+			id,
+			identifier: identifier.to_string(),
+			name: identifier.to_string(),
+			texture_id: id as u32,
+			texture: String::new(),
+			texture_handle: Handle::default(),
+			side_texture_id: id as u32,
+			side_texture: String::new(),
+			side_texture_handle: Handle::default(),
+			move_cost,
+		};
+	}
+
+	#[test]
+	fn tile_type_changes()
+	{
+		let mut app = App::new();
+		app.add_message::<ChangeTileType>();
+		app.add_message::<TileModifiedEvent>();
+		app.add_message::<ChunkModifiedEvent>();
+		app.add_message::<SeaLevelChangedEvent>();
+		app.add_systems(
+			Update,
+			(apply_tile_type_changes, rebuild_retyped_chunks, update_navdata).chain(),
+		);
+
+		let mut tile_assets = Assets::<TileAsset>::default();
+		let mut tile_manager = TileManager::default();
+		tile_manager
+			.register_tile("grass", tile_assets.add(create_tile(0, "grass", 1.)))
+			.unwrap();
+		let road = tile_manager
+			.register_tile("road", tile_assets.add(create_tile(1, "road", 0.5)))
+			.unwrap();
+		let map = Map {
+			chunks: vec![Chunk::default(); 2],
+			height: 1,
+			width: 2,
+			sealevel: -1.,
+			min_level: 0.,
+			max_level: 10.,
+			biome_count: 1,
+			tile_classes: default(),
+		};
+		app.insert_resource(NavData::build(&map, &tile_manager.get_move_costs(&tile_assets)));
+		app.insert_resource(map);
+		app.insert_resource(tile_manager);
+		app.insert_resource(tile_assets);
+		let chunks: Vec<_> = (0..2).map(|_| app.world_mut().spawn_empty().id()).collect();
+		app.insert_resource(PhosChunkRegistry {
+			chunks: chunks.clone(),
+			waters: vec![None; 2],
+		});
+
+		let coord = HexCoord::from_offset_pos(70, 10);
+		app.world_mut().write_message(ChangeTileType { coord, tile_type: road });
+		app.update();
+
+		assert_eq!(app.world().resource::<Map>().get_tile_type(&coord), road);
+		assert_eq!(app.world().resource::<NavData>().get(&coord).move_cost, 0.5);
+		assert!(app.world().entity(chunks[1]).contains::<RebuildChunk>());
+		assert!(!app.world().entity(chunks[0]).contains::<RebuildChunk>());
+	}
 }
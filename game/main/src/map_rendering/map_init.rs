@@ -6,14 +6,18 @@ use bevy_asset_loader::prelude::*;
 use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 use hex::prelude::*;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use shared::states::{AssetLoadState, GameplayState, MenuState};
+use shared::{
+	events::TileModifiedEvent,
+	states::{AssetLoadState, GameplayState, MenuState},
+};
 
 use world_generation::{
 	biome_asset::{BiomeAsset, BiomeAssetPlugin},
 	biome_painter::*,
 	heightmap::generate_heightmap,
-	mapping::biome_map::BiomeMap,
+	mapping::{biome_map::BiomeMap, regions::WorldRegions, slope_map::SlopeMap},
 	prelude::*,
+	texture_atlas::TextureAtlasBuilder,
 	tile_manager::*,
 	tile_mapper::*,
 };
@@ -28,7 +32,7 @@ use crate::{
 	utils::chunk_utils::{paint_map, prepare_chunk_mesh_with_collider},
 };
 
-use super::chunk_rebuild::ChunkRebuildPlugin;
+use super::{chunk_rebuild::ChunkRebuildPlugin, sea_level::SeaLevelPlugin};
 
 pub struct MapInitPlugin;
 
@@ -49,6 +53,7 @@ impl Plugin for MapInitPlugin
 		app.register_asset_reflect::<ExtendedMaterial<StandardMaterial, WaterMaterial>>();
 		app.add_plugins((
 			ChunkRebuildPlugin,
+			SeaLevelPlugin,
 			// TerraFormingTestPlugin,
 			MaterialPlugin::<ExtendedMaterial<StandardMaterial, ChunkMaterial>>::default(),
 			MaterialPlugin::<ExtendedMaterial<StandardMaterial, WaterMaterial>>::default(),
@@ -68,11 +73,16 @@ impl Plugin for MapInitPlugin
 
 		app.add_systems(
 			Update,
-			(finalize_texture, setup_materials, finalize_biome_painter)
+			(
+				(finalize_texture, register_tiles).chain(),
+				setup_materials,
+				finalize_biome_painter,
+			)
 				.run_if(in_state(AssetLoadState::FinalizeAssets)),
 		);
 
 		app.add_systems(Update, despawn_map.run_if(in_state(GeneratorState::Regenerate)));
+		app.add_systems(Update, update_slope_map.run_if(in_state(GeneratorState::Idle)));
 		app.add_systems(
 			Update,
 			(spawn_map, configure_water_material)
@@ -113,26 +123,98 @@ fn finalize_biome_painter(
 	mut next_generator_state: ResMut<NextState<GeneratorState>>,
 	biome_painter: Res<BiomePainterAsset>,
 	biomes: Res<Assets<BiomeAsset>>,
+	asset_server: Res<AssetServer>,
 )
 {
-	let painter = biome_painter.build(&biomes);
+	let painter = biome_painter.build(&biomes, &asset_server);
 	commands.insert_resource(painter);
 	next_generator_state.set(GeneratorState::GenerateHeightmap);
 }
 
+/// Gives every loaded tile a runtime id in the [`TileManager`], sorted by identifier so ids are stable between runs.
+///
+/// Reports duplicate identifiers, textures outside the atlas and tile mappers that refer to tiles that failed to load.
+fn register_tiles(
+	mut tile_manager: ResMut<TileManager>,
+	mut tiles: ResMut<Assets<TileAsset>>,
+	tile_mappers: Res<Assets<TileMapperAsset>>,
+	atlas: Res<PhosAssets>,
+	images: Res<Assets<Image>>,
+	asset_server: Res<AssetServer>,
+)
+{
+	let image = images.get(atlas.handle.id()).expect("Tile atlas is not loaded");
+	let layer_count = image.texture_descriptor.size.depth_or_array_layers;
+
+	let mut ids: Vec<_> = tiles
+		.iter()
+		.map(|(id, tile)| {
+			if !tile.identifier.is_empty() {
+				return (tile.identifier.clone(), id);
+			}
+			//Fall back to the file name without extensions, `grass.tile.ron` is `grass`
+			let identifier = asset_server
+				.get_path(id)
+				.and_then(|p| p.path().file_name().map(|n| n.to_string_lossy().to_string()))
+				.and_then(|n| n.split('.').next().map(|n| n.to_string()))
+				.unwrap_or_else(|| tile.name.clone());
+			return (identifier, id);
+		})
+		.collect();
+	ids.sort();
+	for (identifier, id) in ids {
+		let Some(handle) = tiles.get_strong_handle(id) else {
+			continue;
+		};
+		let Some(mut tile) = tiles.get_mut(id) else {
+			continue;
+		};
+		tile.identifier = identifier.clone();
+		if let Err(err) = tile.validate_textures(layer_count) {
+			error!("{}", err);
+		}
+		match tile_manager.register_tile(&identifier, handle) {
+			Ok(tile_id) => tile.id = tile_id,
+			Err(err) => error!("{}", err),
+		}
+	}
+
+	for (_, mapper) in tile_mappers.iter() {
+		for (path, handle) in mapper.tiles_path.iter().zip(&mapper.tiles) {
+			if !tiles.contains(handle.id()) {
+				error!("{}", TileRegistryError::MissingTile(path.clone()));
+			}
+		}
+	}
+	info!("Registered {} tiles", tile_manager.tiles.len());
+}
+
+/// Size of the atlas layers when no tile texture could be loaded
+const TILE_TEXTURE_SIZE: u32 = 256;
+
+/// Creates the tile texture array, built from the textures of the tiles when they define any,
+/// otherwise the pre-stacked atlas image is used as is.
+///
+/// The built array starts with the layers of the pre-stacked atlas, so tiles without a texture keep their layers
 fn finalize_texture(
 	mut atlas: ResMut<PhosAssets>,
 	mut images: ResMut<Assets<Image>>,
+	mut tiles: ResMut<Assets<TileAsset>>,
 	mut chunk_materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, ChunkMaterial>>>,
 	mut next_load_state: ResMut<NextState<AssetLoadState>>,
 )
 {
-	let mut image = images.get_mut(atlas.handle.id()).unwrap();
-
-	let array_layers = image.height() / image.width();
-	image
-		.reinterpret_stacked_2d_as_array(array_layers)
-		.expect("Failed to reinterpret as array");
+	if tiles.iter().any(|(_, tile)| !tile.texture.is_empty()) {
+		let image = build_tile_atlas(&mut tiles, &images, images.get(atlas.handle.id()));
+		atlas.handle = images.add(image);
+	} else {
+		let mut image = images.get_mut(atlas.handle.id()).unwrap();
+
+		let array_layers = image.height() / image.width();
+		image
+			.reinterpret_stacked_2d_as_array(array_layers)
+			.expect("Failed to reinterpret as array");
+	}
 
 	let chunk_material = chunk_materials.add(ExtendedMaterial {
 		base: StandardMaterial::default(),
@@ -145,6 +227,84 @@ fn finalize_texture(
 	next_load_state.set(AssetLoadState::LoadComplete);
 }
 
+/// Packs the textures of every tile into the layers of a new array texture and points the tiles at their layers.
+///
+/// The layers of the `stacked` atlas are copied first and keep their index
+fn build_tile_atlas(tiles: &mut Assets<TileAsset>, images: &Assets<Image>, stacked: Option<&Image>) -> Image
+{
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Build Tile Atlas").entered();
+	let size = tiles
+		.iter()
+		.filter_map(|(_, tile)| images.get(tile.texture_handle.id()))
+		.chain(stacked)
+		.map(|image| image.width())
+		.max()
+		.unwrap_or(TILE_TEXTURE_SIZE);
+	let mut builder = TextureAtlasBuilder::new(size);
+	let stacked_layers = match stacked.map(|image| image.clone().try_into_dynamic()) {
+		Some(Ok(image)) => {
+			builder.add_stacked("stacked_atlas", &image);
+			builder.get_layer_count()
+		}
+		Some(Err(err)) => {
+			error!("Failed to read the pre-stacked tile atlas: {}", err);
+			0
+		}
+		None => 0,
+	};
+
+	let ids: Vec<_> = tiles.ids().collect();
+	for id in ids {
+		let Some(mut tile) = tiles.get_mut(id) else {
+			continue;
+		};
+		if tile.texture.is_empty() {
+			if tile.texture_id >= stacked_layers || tile.side_texture_id >= stacked_layers {
+				error!(
+					"Tile {} has no texture and its layers are not in the pre-stacked atlas",
+					tile.name
+				);
+			}
+			continue;
+		}
+		let Some(texture_id) = add_atlas_layer(&mut builder, images, &tile.texture, &tile.texture_handle) else {
+			continue;
+		};
+		tile.texture_id = texture_id;
+		tile.side_texture_id = if tile.side_texture.is_empty() {
+			texture_id
+		} else {
+			add_atlas_layer(&mut builder, images, &tile.side_texture, &tile.side_texture_handle).unwrap_or(texture_id)
+		};
+	}
+	info!("Built tile atlas with {} layers", builder.get_layer_count());
+	return builder.build(true);
+}
+
+fn add_atlas_layer(
+	builder: &mut TextureAtlasBuilder,
+	images: &Assets<Image>,
+	path: &str,
+	handle: &Handle<Image>,
+) -> Option<u32>
+{
+	if let Some(layer) = builder.get_layer(path) {
+		return Some(layer);
+	}
+	let Some(image) = images.get(handle.id()) else {
+		error!("Tile texture {} is not loaded", path);
+		return None;
+	};
+	match image.clone().try_into_dynamic() {
+		Ok(image) => return Some(builder.add(path, &image)),
+		Err(err) => {
+			error!("Failed to read tile texture {}: {}", path, err);
+			return None;
+		}
+	}
+}
+
 fn create_heightmap(
 	mut commands: Commands,
 	mut next_state: ResMut<NextState<GeneratorState>>,
@@ -203,8 +363,10 @@ fn create_heightmap(
 		// size: UVec2::splat(1),
 	};
 	let (heightmap, biome_map) = generate_heightmap(&config, 42069, &biome_painter);
+	let regions = WorldRegions::generate(&heightmap, &biome_map, &biome_painter, 42069);
 
 	commands.insert_resource(heightmap);
+	commands.insert_resource(regions);
 	commands.insert_resource(biome_map);
 	commands.insert_resource(config);
 	next_state.set(GeneratorState::SpawnMap);
@@ -234,10 +396,12 @@ fn spawn_map(
 	mut game_state: ResMut<NextState<MenuState>>,
 	mut gameplay_state: ResMut<NextState<GameplayState>>,
 	biome_painter: Res<BiomePainter>,
+	biome_map: Res<BiomeMap>,
 )
 {
 	info!("Spawn Map");
-	paint_map(&mut heightmap, &biome_painter, &tile_assets, &tile_mappers);
+	paint_map(&mut heightmap, &biome_map, &biome_painter, &tile_assets, &tile_mappers);
+	commands.insert_resource(SlopeMap::build(&heightmap));
 
 	let root = commands.spawn(MapRoot).id();
 	//Prepare Mesh Data
@@ -280,6 +444,7 @@ fn spawn_map(
 						water_mesh_handle.clone(),
 						atlas.water_material.clone(),
 						index,
+						heightmap.sealevel,
 					))
 					.id();
 				commands
@@ -306,10 +471,30 @@ fn spawn_map(
 	}
 }
 
+fn update_slope_map(
+	mut tile_updates: MessageReader<TileModifiedEvent>,
+	mut slope_map: ResMut<SlopeMap>,
+	heightmap: Res<Map>,
+)
+{
+	let tiles: Vec<_> = tile_updates
+		.read()
+		.filter_map(|event| match event {
+			TileModifiedEvent::HeightChanged(coord, _) => Some(*coord),
+			_ => None,
+		})
+		.collect();
+	if tiles.is_empty() {
+		return;
+	}
+	slope_map.update_tiles(&heightmap, &tiles);
+}
+
 fn despawn_map(
 	mut commands: Commands,
 	mut heightmap: ResMut<Map>,
 	mut biome_map: ResMut<BiomeMap>,
+	mut regions: ResMut<WorldRegions>,
 	cfg: Res<GenerationConfig>,
 	map_root: Single<Entity, With<MapRoot>>,
 	mut next_state: ResMut<NextState<GeneratorState>>,
@@ -320,5 +505,6 @@ fn despawn_map(
 	commands.entity(map_root.into_inner()).despawn();
 
 	(*heightmap, *biome_map) = generate_heightmap(&cfg, 4, &biome_painter);
+	*regions = WorldRegions::generate(&heightmap, &biome_map, &biome_painter, 4);
 	next_state.set(GeneratorState::SpawnMap);
 }
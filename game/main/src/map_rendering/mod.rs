@@ -2,6 +2,7 @@ pub mod chunk_rebuild;
 pub mod map_init;
 pub mod prelude;
 pub mod render_distance_system;
+pub mod sea_level;
 #[cfg(feature = "terraforming")]
 pub mod terraforming_test;
 pub mod prefabs;
@@ -4,7 +4,10 @@ use avian3d::{
 };
 use bevy::{light::NotShadowCaster, prelude::*};
 
-use crate::{map_rendering::render_distance_system::RenderDistanceVisibility, prelude::PhosChunk};
+use crate::{
+	map_rendering::render_distance_system::RenderDistanceVisibility,
+	prelude::{PhosChunk, WaterSurface},
+};
 
 pub struct ChunkPrefab;
 impl ChunkPrefab
@@ -31,7 +34,13 @@ impl ChunkPrefab
 		)
 	}
 
-	pub fn water(translation: Vec3, mesh: Handle<Mesh>, material: Handle<impl Material>, index: usize) -> impl Bundle
+	pub fn water(
+		translation: Vec3,
+		mesh: Handle<Mesh>,
+		material: Handle<impl Material>,
+		index: usize,
+		sealevel: f32,
+	) -> impl Bundle
 	{
 		(
 			Mesh3d(mesh),
@@ -39,6 +48,7 @@ impl ChunkPrefab
 			Transform::from_translation(translation),
 			Name::new(format!("Water {}", index)),
 			PhosChunk::new(index),
+			WaterSurface { level: sealevel },
 			NotShadowCaster,
 		)
 	}
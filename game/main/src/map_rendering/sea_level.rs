@@ -0,0 +1,116 @@
+use std::f32::consts::TAU;
+
+use bevy::{pbr::ExtendedMaterial, prelude::*};
+use bevy_inspector_egui::quick::ResourceInspectorPlugin;
+use shared::events::{ChunkModifiedEvent, SeaLevelChangedEvent};
+use world_generation::{prelude::Map, states::GeneratorState};
+
+use crate::{
+	prelude::{PhosAssets, PhosChunkRegistry, RebuildChunk, WaterSurface},
+	shader_extensions::water_material::WaterMaterial,
+};
+
+pub struct SeaLevelPlugin;
+
+impl Plugin for SeaLevelPlugin
+{
+	fn build(&self, app: &mut App)
+	{
+		app.register_type::<SeaLevel>();
+		app.init_resource::<SeaLevel>();
+		app.add_plugins(ResourceInspectorPlugin::<SeaLevel>::default());
+		app.add_systems(PostUpdate, init_sea_level.run_if(in_state(GeneratorState::SpawnMap)));
+		app.add_systems(Update, update_sea_level.run_if(in_state(GeneratorState::Idle)));
+	}
+}
+
+/// Drives the sea level of the map at runtime, insert it before the [`SeaLevelPlugin`] to enable tides
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct SeaLevel
+{
+	/// Sea level without tides, raise or lower it to flood or drain the map
+	pub base: f32,
+	/// How far the tide moves above and below `base`, tides are off while it is zero
+	pub tide_amplitude: f32,
+	/// Length of a full tide cycle in seconds
+	pub tide_period: f32,
+	/// How far the level has to move before the map is updated, keeps chunks from rebuilding every frame
+	pub step: f32,
+}
+
+impl Default for SeaLevel
+{
+	fn default() -> Self
+	{
+		Self {
+			base: 0.,
+			tide_amplitude: 0.,
+			tide_period: 600.,
+			step: 0.1,
+		}
+	}
+}
+
+impl SeaLevel
+{
+	/// A sea level that moves by `amplitude` above and below its base every `period` seconds
+	pub fn with_tides(amplitude: f32, period: f32) -> Self
+	{
+		return Self {
+			tide_amplitude: amplitude,
+			tide_period: period,
+			..default()
+		};
+	}
+
+	pub fn get_level(&self, time: f32) -> f32
+	{
+		if self.tide_amplitude == 0. || self.tide_period <= 0. {
+			return self.base;
+		}
+		return self.base + (time * TAU / self.tide_period).sin() * self.tide_amplitude;
+	}
+}
+
+fn init_sea_level(mut sea_level: ResMut<SeaLevel>, heightmap: Res<Map>)
+{
+	sea_level.base = heightmap.sealevel;
+}
+
+fn update_sea_level(
+	mut commands: Commands,
+	sea_level: Res<SeaLevel>,
+	time: Res<Time>,
+	mut heightmap: ResMut<Map>,
+	chunks: Res<PhosChunkRegistry>,
+	atlas: Res<PhosAssets>,
+	mut water_materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, WaterMaterial>>>,
+	mut water_surfaces: Query<(&mut Transform, &WaterSurface)>,
+	mut chunk_modified: MessageWriter<ChunkModifiedEvent>,
+	mut sealevel_changed: MessageWriter<SeaLevelChangedEvent>,
+)
+{
+	let level = sea_level.get_level(time.elapsed_secs());
+	if (level - heightmap.sealevel).abs() < sea_level.step {
+		return;
+	}
+
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Update Sea Level").entered();
+	let tiles = heightmap.set_sealevel(level);
+
+	//Only chunks where the coastline moved need new meshes, the rest of the water is just moved
+	for index in heightmap.get_chunks_in_range(&tiles, Map::LAND_DISTANCE_RANGE) {
+		chunk_modified.write(ChunkModifiedEvent { index });
+		commands.entity(chunks.chunks[index]).insert(RebuildChunk);
+	}
+	for (mut transform, surface) in &mut water_surfaces {
+		transform.translation.y = level - surface.level;
+	}
+	if let Some(mut material) = water_materials.get_mut(atlas.water_material.id()) {
+		material.extension.settings.surface_level = level;
+	}
+
+	sealevel_changed.write(SeaLevelChangedEvent { sealevel: level, tiles });
+}
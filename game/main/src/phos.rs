@@ -6,6 +6,7 @@ use crate::map_rendering::render_distance_system::RenderDistancePlugin;
 use crate::map_rendering::terraforming_test::TerraFormingTestPlugin;
 use crate::ui::build_ui::BuildUIPlugin;
 use crate::ui::ui_base::BaseUIPlugin;
+use crate::utils::content_packs::{discover_content_packs, get_pack_assets};
 #[cfg(debug_assertions)]
 use crate::utils::debug_plugin::DebugPlugin;
 use crate::utils::tile_selection_plugin::TileSelectionPlugin;
@@ -17,6 +18,7 @@ use bevy::light::CascadeShadowConfig;
 use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use resources::resource_plugin::ResourcePlugin;
 use shared::sets::GameplaySystems;
 use shared::states::{GameplayState, MenuState};
 use shared::{despawn::DespawnPlugin, states::AssetLoadState};
@@ -33,14 +35,19 @@ impl Plugin for PhosGamePlugin
 		app.insert_state(MenuState::Loading);
 		app.insert_state(GameplayState::Waiting);
 
+		let content_packs = discover_content_packs();
 		app.add_loading_state(
-			LoadingState::new(AssetLoadState::Loading).continue_to_state(AssetLoadState::FinalizeAssets),
+			LoadingState::new(AssetLoadState::Loading)
+				.continue_to_state(AssetLoadState::FinalizeAssets)
+				.add_standard_dynamic_assets(get_pack_assets(&content_packs)),
 		);
+		app.insert_resource(content_packs);
 
 		app.add_plugins((
 			PhosCameraPlugin,
 			MapInitPlugin,
 			RenderDistancePlugin,
+			ResourcePlugin,
 			// BuildingPugin,
 			BaseUIPlugin,
 			BuildUIPlugin,
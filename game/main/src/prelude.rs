@@ -37,6 +37,13 @@ pub struct MapRoot;
 #[derive(Component)]
 pub struct WaterMesh(pub AssetId<Mesh>, pub Entity);
 
+/// Sea level a water mesh was built at, the mesh is offset when the sea level changes without a rebuild
+#[derive(Component)]
+pub struct WaterSurface
+{
+	pub level: f32,
+}
+
 #[derive(Resource, Default)]
 pub struct PhosChunkRegistry
 {
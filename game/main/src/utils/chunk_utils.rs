@@ -3,49 +3,88 @@ use avian3d::prelude::*;
 use bevy::log::*;
 use bevy::prelude::*;
 use hex::prelude::*;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use noise::{NoiseFn, SuperSimplex};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use world_generation::{
 	biome_painter::BiomePainter,
 	generators::{
 		chunk_colliders::generate_chunk_collider,
 		mesh_generator::{generate_chunk_mesh, generate_chunk_water_mesh},
 	},
+	mapping::biome_map::BiomeMap,
 	prelude::{Map, MeshChunkData},
 	tile_manager::TileAsset,
-	tile_mapper::TileMapperAsset,
+	tile_mapper::{TileMapperAsset, TileSample},
 };
 
+/// Scale of the noise that dithers the edges between painted tiles
+const PAINT_NOISE_SCALE: f64 = 0.15;
+const PAINT_NOISE_SEED: u32 = 0x7A11E5;
+
 pub fn paint_map(
 	map: &mut Map,
+	biome_map: &BiomeMap,
 	painter: &BiomePainter,
 	tiles: &Res<Assets<TileAsset>>,
 	mappers: &Res<Assets<TileMapperAsset>>,
 )
 {
-	map.chunks.par_iter_mut().for_each(|chunk: &mut Chunk| {
-		paint_chunk(chunk, painter, tiles, mappers);
-	});
+	#[cfg(feature = "tracing")]
+	let _span = info_span!("Paint Map").entered();
+	let noise = SuperSimplex::new(PAINT_NOISE_SEED);
+	let painted: Vec<_> = (0..map.chunks.len())
+		.into_par_iter()
+		.map(|index| paint_chunk(map, index, biome_map, painter, tiles, mappers, &noise))
+		.collect();
+	for (chunk, (textures, tile_types)) in map.chunks.iter_mut().zip(painted) {
+		chunk.textures = textures;
+		chunk.tile_types = tile_types;
+	}
 }
 
+/// Picks the textures and tile type of every tile in a chunk with the tile mapper of its biome
 pub fn paint_chunk(
-	chunk: &mut Chunk,
+	map: &Map,
+	chunk_index: usize,
+	biome_map: &BiomeMap,
 	painter: &BiomePainter,
 	tiles: &Res<Assets<TileAsset>>,
 	mappers: &Res<Assets<TileMapperAsset>>,
-)
+	noise: &impl NoiseFn<f64, 2>,
+) -> ([[u32; 2]; Chunk::AREA], [usize; Chunk::AREA])
 {
+	let chunk = &map.chunks[chunk_index];
+	let cx = chunk.chunk_offset.x as usize * Chunk::SIZE;
+	let cz = chunk.chunk_offset.y as usize * Chunk::SIZE;
+	let mut textures = [[0; 2]; Chunk::AREA];
+	let mut tile_types = [0; Chunk::AREA];
 	for z in 0..Chunk::SIZE {
 		for x in 0..Chunk::SIZE {
 			let idx = x + z * Chunk::SIZE;
 			let height = chunk.heights[idx];
 			let biome_id = chunk.biome_id[idx];
 			let biome = &painter.biomes[biome_id];
-			let mapper = mappers.get(biome.tile_mapper.id());
-			let tile_handle = mapper.unwrap().sample_tile(height);
+			let mapper = mappers.get(biome.tile_mapper.id()).unwrap();
+			let tile_handle = if mapper.has_rules() {
+				let (tx, tz) = (x + cx, z + cz);
+				let coord = HexCoord::from_offset_pos(tx, tz);
+				let sample = TileSample {
+					height,
+					slope: map.slope_at(&coord),
+					water_distance: map.get_distance_to_water(&coord, mapper.get_water_distance_range()),
+					moisture: biome_map.get_biome_data(tx, tz).moisture,
+					noise: noise.get([tx as f64 * PAINT_NOISE_SCALE, tz as f64 * PAINT_NOISE_SCALE]) as f32,
+				};
+				mapper.sample_tile_with_rules(&sample)
+			} else {
+				mapper.sample_tile(height)
+			};
 			let tile = tiles.get(tile_handle).unwrap();
-			chunk.textures[idx] = [tile.texture_id, tile.side_texture_id];
+			textures[idx] = [tile.texture_id, tile.side_texture_id];
+			tile_types[idx] = tile.id;
 		}
 	}
+	return (textures, tile_types);
 }
 
 pub fn prepare_chunk_mesh(chunk: &MeshChunkData, sealevel: f32, map_size: UVec2) -> (Mesh, Option<Mesh>)
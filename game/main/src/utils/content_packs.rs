@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use bevy_asset_loader::prelude::*;
+use shared::{content_pack::ContentPacks, content_registry::ContentKind};
+
+/// Reads the content packs of the asset folder, packs that fail to load are logged and left out
+pub fn discover_content_packs() -> ContentPacks
+{
+	let asset_root = FileAssetReader::get_base_path().join(AssetPlugin::default().file_path);
+	let (packs, errors) = ContentPacks::discover(&asset_root);
+	for err in errors {
+		error!("{}", err);
+	}
+	for pack in packs.iter() {
+		info!(
+			"Loading content pack {} {} from {}",
+			pack.manifest.name, pack.manifest.version, pack.asset_path
+		);
+	}
+	return packs;
+}
+
+/// The dynamic assets behind the `pack_*` keys the content databases load pack content with
+pub fn get_pack_assets(packs: &ContentPacks) -> HashMap<String, StandardDynamicAsset>
+{
+	let keys = [
+		("pack_buildings", ContentKind::Building),
+		("pack_units", ContentKind::Unit),
+		("pack_resources", ContentKind::Resource),
+		("pack_biomes", ContentKind::Biome),
+	];
+	return keys
+		.into_iter()
+		.map(|(key, kind)| {
+			let paths = packs.get_files(kind);
+			return (key.to_string(), StandardDynamicAsset::Files { paths });
+		})
+		.collect();
+}
@@ -4,8 +4,13 @@ use bevy_inspector_egui::bevy_egui::{EguiContexts, EguiTextureHandle};
 use bevy_inspector_egui::egui::{self};
 use image::{ImageBuffer, Rgba};
 use world_generation::biome_asset::BiomeAsset;
-use world_generation::biome_painter::BiomePainterAsset;
+use world_generation::biome_painter::{BiomePainter, BiomePainterAsset};
 use world_generation::mapping::biome_map::BiomeMap;
+use world_generation::mapping::map_export::export_map;
+use world_generation::mapping::map_renderer::{
+	BiomeRenderer, ContourRenderer, HillshadeRenderer, HypsometricRenderer, SlopeRenderer, render_with,
+};
+use world_generation::mapping::map_stats::MapStats;
 use world_generation::mapping::map_utils::{render_biome_map, render_biome_noise_map};
 use world_generation::{mapping::map_utils::render_map, prelude::Map, states::GeneratorState};
 
@@ -17,7 +22,10 @@ impl Plugin for EditorPlugin
 	{
 		app.init_resource::<UIState>();
 
-		app.add_systems(PostUpdate, prepare_image.run_if(in_state(GeneratorState::SpawnMap)));
+		app.add_systems(
+			PostUpdate,
+			(prepare_image, update_map_stats).run_if(in_state(GeneratorState::SpawnMap)),
+		);
 		app.add_systems(
 			Update,
 			(render_map_ui, update_map_render, asset_reloaded).run_if(in_state(GeneratorState::Idle)),
@@ -36,6 +44,11 @@ pub fn prepare_image(mut images: ResMut<Assets<Image>>, heightmap: Res<Map>, mut
 	commands.insert_resource(MapImage(handle));
 }
 
+pub fn update_map_stats(heightmap: Res<Map>, biome_map: Res<BiomeMap>, mut commands: Commands)
+{
+	commands.insert_resource(MapStats::compute(&heightmap, &biome_map));
+}
+
 #[derive(Resource)]
 struct UIState
 {
@@ -66,12 +79,18 @@ enum MapDisplayType
 	BiomeNoiseTemp,
 	BiomeNoiseContinent,
 	BiomeNoiseMoisture,
+	Hypsometric,
+	Hillshade,
+	Slope,
+	BiomeBlend,
+	Contours,
 }
 
 fn asset_reloaded(
 	mut asset_events: MessageReader<AssetEvent<BiomeAsset>>,
 	biomes: Res<Assets<BiomeAsset>>,
 	biome_painter: Res<BiomePainterAsset>,
+	asset_server: Res<AssetServer>,
 	mut commands: Commands,
 )
 {
@@ -82,7 +101,7 @@ fn asset_reloaded(
 		}
 	}
 	if rebuild {
-		let painter = biome_painter.build(&biomes);
+		let painter = biome_painter.build(&biomes, &asset_server);
 		commands.insert_resource(painter);
 	}
 }
@@ -91,6 +110,8 @@ fn render_map_ui(
 	image: Res<MapImage>,
 	heightmap: Res<Map>,
 	biome_map: Res<BiomeMap>,
+	stats: Res<MapStats>,
+	biome_painter: Res<BiomePainter>,
 	mut contexts: EguiContexts,
 	mut state: ResMut<UIState>,
 )
@@ -121,6 +142,11 @@ fn render_map_ui(
 					MapDisplayType::BiomeNoiseMoisture,
 					"Biome Noise: Moisture",
 				);
+				ui.selectable_value(&mut map_type, MapDisplayType::Hypsometric, "Hypsometric");
+				ui.selectable_value(&mut map_type, MapDisplayType::Hillshade, "Hillshade");
+				ui.selectable_value(&mut map_type, MapDisplayType::Slope, "Slope");
+				ui.selectable_value(&mut map_type, MapDisplayType::BiomeBlend, "Biome Blend");
+				ui.selectable_value(&mut map_type, MapDisplayType::Contours, "Contours");
 			});
 
 		ui.add(egui::widgets::Image::new(egui::load::SizedTexture::new(
@@ -132,11 +158,64 @@ fn render_map_ui(
 			let img = get_map_image(&heightmap, &biome_map, map_type);
 			_ = img.save(format!("{:?}.png", map_type));
 		}
+		if ui.button("Export Map").clicked() {
+			match export_map(&heightmap, &biome_map, "export", "map") {
+				Ok(meta) => info!("Exported {} map files", meta.files.len()),
+				Err(err) => error!("Failed to export map: {}", err),
+			}
+		}
+
+		egui::CollapsingHeader::new("Stats").show(ui, |ui| {
+			render_stats_ui(ui, &stats, &biome_painter);
+			if ui.button("Save Stats").clicked() {
+				match stats.to_json() {
+					Ok(json) => {
+						if let Err(err) = std::fs::write("map_stats.json", json) {
+							error!("Failed to save map stats: {}", err);
+						}
+					}
+					Err(err) => error!("Failed to serialize map stats: {}", err),
+				}
+			}
+		});
 	});
 
 	state.target_map_type = map_type;
 }
 
+fn render_stats_ui(ui: &mut egui::Ui, stats: &MapStats, biome_painter: &BiomePainter)
+{
+	ui.label(format!(
+		"Height: {:.1} to {:.1}, mean {:.1}",
+		stats.min_height, stats.max_height, stats.mean_height
+	));
+	ui.label(format!(
+		"Land: {:.1}% ({} tiles), Water: {} tiles",
+		stats.land_ratio * 100.,
+		stats.land_tiles,
+		stats.water_tiles
+	));
+	ui.label(format!(
+		"Landmasses: {}, largest {} tiles",
+		stats.landmass_count, stats.largest_landmass
+	));
+	ui.label(format!("Coastline: {} edges", stats.coastline_length));
+	ui.label(format!("Mean slope: {:.2}", stats.mean_slope));
+	ui.label(format!(
+		"Buildable: {} tiles ({:.1}% of land)",
+		stats.buildable_tiles,
+		stats.buildable_ratio * 100.
+	));
+	ui.separator();
+	for biome in &stats.biomes {
+		let name = biome_painter
+			.biomes
+			.get(biome.id)
+			.map_or(format!("Biome {}", biome.id), |b| b.name.clone());
+		ui.label(format!("{}: {:.1}%", name, biome.percent));
+	}
+}
+
 fn update_map_render(
 	mut state: ResMut<UIState>,
 	mut images: ResMut<Assets<Image>>,
@@ -169,5 +248,10 @@ fn get_map_image(heightmap: &Map, biome_map: &BiomeMap, map_type: MapDisplayType
 		MapDisplayType::BiomeNoiseTemp => render_biome_noise_map(biome_map, Vec3::X),
 		MapDisplayType::BiomeNoiseContinent => render_biome_noise_map(biome_map, Vec3::Y),
 		MapDisplayType::BiomeNoiseMoisture => render_biome_noise_map(biome_map, Vec3::Z),
+		MapDisplayType::Hypsometric => render_with(&HypsometricRenderer::default(), heightmap, biome_map),
+		MapDisplayType::Hillshade => render_with(&HillshadeRenderer::default(), heightmap, biome_map),
+		MapDisplayType::Slope => render_with(&SlopeRenderer::default(), heightmap, biome_map),
+		MapDisplayType::BiomeBlend => render_with(&BiomeRenderer::default(), heightmap, biome_map),
+		MapDisplayType::Contours => render_with(&ContourRenderer::default(), heightmap, biome_map),
 	};
 }
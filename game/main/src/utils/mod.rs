@@ -1,4 +1,5 @@
 pub mod chunk_utils;
+pub mod content_packs;
 #[cfg(debug_assertions)]
 pub mod debug_plugin;
 #[cfg(feature = "editor")]
@@ -1,3 +1,4 @@
+pub mod placement_rule;
 pub mod traits;
 mod validation_result;
 pub mod validators;
@@ -0,0 +1,181 @@
+use std::fmt::Display;
+
+use hex::prelude::HexCoord;
+use serde::{Deserialize, Serialize};
+use world_generation::{
+	mapping::map::Map,
+	tile_manager::{TileManager, TileRegistryError},
+};
+
+use crate::{
+	ValidationResult,
+	traits::PlacementValidator,
+	validators::{Always, NextToWater, OnLand, OnTileType, OnWater},
+};
+
+/// Data driven form of the validators, so content files can say where a building may be placed
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub enum PlacementRule
+{
+	Always,
+	#[default]
+	OnLand,
+	OnWater,
+	NextToWater,
+	OnTileType(TileTypes),
+	Not(Box<PlacementRule>),
+	Or(Box<PlacementRule>, Box<PlacementRule>),
+	/// Valid when every rule is valid
+	All(Vec<PlacementRule>),
+}
+
+/// Tile types of a rule, content files list tile identifiers that are resolved once the tiles are registered
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct TileTypes
+{
+	pub identifiers: Vec<String>,
+	/// Runtime ids of the tiles, empty until [`PlacementRule::resolve_tiles`] ran
+	ids: Vec<usize>,
+}
+
+impl From<Vec<String>> for TileTypes
+{
+	fn from(identifiers: Vec<String>) -> Self
+	{
+		TileTypes {
+			identifiers,
+			ids: Vec::new(),
+		}
+	}
+}
+
+impl From<TileTypes> for Vec<String>
+{
+	fn from(tile_types: TileTypes) -> Self
+	{
+		tile_types.identifiers
+	}
+}
+
+impl PlacementRule
+{
+	/// Resolves the tile identifiers of every [`PlacementRule::OnTileType`] to runtime ids,
+	/// the errors list every unknown tile
+	pub fn resolve_tiles(&mut self, tile_manager: &TileManager) -> Result<(), Vec<TileRegistryError>>
+	{
+		let mut errors = Vec::new();
+		self.resolve_tiles_into(tile_manager, &mut errors);
+		if errors.is_empty() { Ok(()) } else { Err(errors) }
+	}
+
+	fn resolve_tiles_into(&mut self, tile_manager: &TileManager, errors: &mut Vec<TileRegistryError>)
+	{
+		match self {
+			PlacementRule::OnTileType(tile_types) => {
+				tile_types.ids.clear();
+				for identifier in &tile_types.identifiers {
+					match tile_manager.get_id(identifier) {
+						Ok(id) => tile_types.ids.push(id),
+						Err(err) => errors.push(err),
+					}
+				}
+			}
+			PlacementRule::Not(rule) => rule.resolve_tiles_into(tile_manager, errors),
+			PlacementRule::Or(left, right) => {
+				left.resolve_tiles_into(tile_manager, errors);
+				right.resolve_tiles_into(tile_manager, errors);
+			}
+			PlacementRule::All(rules) => {
+				for rule in rules {
+					rule.resolve_tiles_into(tile_manager, errors);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	/// Validates every tile of a footprint, returns the first tile that fails
+	pub fn validate_footprint(&self, footprint: &[HexCoord], map: &Map) -> Result<(), HexCoord>
+	{
+		match footprint
+			.iter()
+			.find(|coord| !map.is_in_bounds(coord) || !self.validate_placement(**coord, map).is_valid)
+		{
+			Some(coord) => Err(*coord),
+			None => Ok(()),
+		}
+	}
+}
+
+impl PlacementValidator for PlacementRule
+{
+	type Inner = Always;
+
+	fn validate_placement(&self, pos: HexCoord, map: &Map) -> ValidationResult
+	{
+		match self {
+			PlacementRule::Not(rule) => {
+				let mut result = rule.validate_placement(pos, map);
+				result.is_valid = !result.is_valid;
+				result
+			}
+			PlacementRule::Or(left, right) => {
+				let left = left.validate_placement(pos, map);
+				if left.is_valid {
+					return left;
+				}
+				right.validate_placement(pos, map)
+			}
+			PlacementRule::All(rules) => ValidationResult {
+				is_valid: rules.iter().all(|rule| rule.validate_placement(pos, map).is_valid),
+				..Default::default()
+			},
+			_ => self.validate_self(pos, map),
+		}
+	}
+
+	fn validate_self(&self, pos: HexCoord, map: &Map) -> ValidationResult
+	{
+		match self {
+			PlacementRule::Always => ValidationResult {
+				is_valid: true,
+				..Default::default()
+			},
+			PlacementRule::OnLand => OnLand::<Always>::default().validate_placement(pos, map),
+			PlacementRule::OnWater => OnWater::<Always>::default().validate_placement(pos, map),
+			PlacementRule::NextToWater => NextToWater::<Always>::default().validate_placement(pos, map),
+			PlacementRule::OnTileType(tile_types) => OnTileType::<Always> {
+				tile_types: tile_types.ids.clone(),
+				..Default::default()
+			}
+			.validate_placement(pos, map),
+			_ => ValidationResult::default(),
+		}
+	}
+
+	fn get_inner(&self) -> Option<&Self::Inner>
+	{
+		None
+	}
+}
+
+impl Display for PlacementRule
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		match self {
+			PlacementRule::Always => write!(f, "anywhere"),
+			PlacementRule::OnLand => write!(f, "on land"),
+			PlacementRule::OnWater => write!(f, "on water"),
+			PlacementRule::NextToWater => write!(f, "next to water"),
+			PlacementRule::OnTileType(tile_types) => write!(f, "on {}", tile_types.identifiers.join(" or ")),
+			PlacementRule::Not(rule) => write!(f, "not {}", rule),
+			PlacementRule::Or(left, right) => write!(f, "{} or {}", left, right),
+			PlacementRule::All(rules) => {
+				let rules: Vec<_> = rules.iter().map(|rule| rule.to_string()).collect();
+				write!(f, "{}", rules.join(" and "))
+			}
+		}
+	}
+}
@@ -1,22 +1,26 @@
 use hex::prelude::{Chunk, HexCoord};
-use world_generation::mapping::map::Map;
+use world_generation::{
+	mapping::map::Map,
+	tile_manager::{TileManager, TileRegistryError},
+};
 
 use crate::{
+	placement_rule::PlacementRule,
 	traits::PlacementValidator,
-	validators::{NextToWater, Not, OnLand, OnWater, Or},
+	validators::{NextToWater, Not, OnLand, OnTileType, OnWater, Or},
 };
 
 #[test]
 fn on_land()
 {
 	let map = create_test_map();
-	let pos = HexCoord::from_offset_pos(1, 1);
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
 	let v: OnLand = OnLand::default();
 
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: Expected on land");
 
-	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	let pos = HexCoord::from_offset_pos(1, 1);
 	let result = v.validate_placement(pos, &map);
 	assert!(!result.is_valid, "Validation failed: Expected on water");
 }
@@ -25,13 +29,13 @@ fn on_land()
 fn on_water()
 {
 	let map = create_test_map();
-	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	let pos = HexCoord::from_offset_pos(1, 1);
 	let v: OnWater = Default::default();
 
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: Expected on water");
 
-	let pos = HexCoord::from_offset_pos(1, 1);
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
 	let result = v.validate_placement(pos, &map);
 	assert!(!result.is_valid, "Validation failed: Expected on land");
 }
@@ -40,14 +44,14 @@ fn on_water()
 fn not()
 {
 	let map = create_test_map();
-	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	let pos = HexCoord::from_offset_pos(1, 1);
 
 	let v: Not<OnLand> = Default::default();
 
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: Expected on water");
 
-	let pos = HexCoord::from_offset_pos(1, 1);
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
 	let result = v.validate_placement(pos, &map);
 	assert!(!result.is_valid, "Validation failed: Expected on land");
 }
@@ -56,14 +60,14 @@ fn not()
 fn or()
 {
 	let map = create_test_map();
-	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	let pos = HexCoord::from_offset_pos(1, 1);
 
 	let v: Or<OnLand, OnWater> = Default::default();
 
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: Expected on water");
 
-	let pos = HexCoord::from_offset_pos(1, 1);
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: Expected on land");
 }
@@ -88,9 +92,88 @@ fn on_shore()
 	let result = v.validate_placement(pos, &map);
 	assert!(result.is_valid, "Validation failed: On shore");
 
-	let pos = HexCoord::from_offset_pos(1, 1);
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
 	let result = v.validate_placement(pos, &map);
 	assert!(!result.is_valid, "Validation failed: Inland");
+
+	let pos = HexCoord::from_offset_pos(1, 1);
+	let result = v.validate_placement(pos, &map);
+	assert!(!result.is_valid, "Validation failed: In water");
+}
+
+#[test]
+fn on_tile_type()
+{
+	let mut map = create_test_map();
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	map.chunks[0].tile_types[pos.to_chunk_local_index()] = 2;
+	let v: OnTileType<OnLand> = OnTileType {
+		tile_types: vec![1, 2],
+		..Default::default()
+	};
+
+	let result = v.validate_placement(pos, &map);
+	assert!(result.is_valid, "Validation failed: Expected tile type 2");
+
+	let pos = HexCoord::from_offset_pos(Chunk::SIZE - 2, Chunk::SIZE - 1);
+	let result = v.validate_placement(pos, &map);
+	assert!(!result.is_valid, "Validation failed: Expected tile type 0");
+}
+
+#[test]
+fn placement_rule()
+{
+	let mut map = create_test_map();
+	let land = HexCoord::from_offset_pos(Chunk::SIZE - 1, Chunk::SIZE - 1);
+	let water = HexCoord::from_offset_pos(1, 1);
+	let rule = PlacementRule::default();
+
+	assert!(
+		rule.validate_placement(land, &map).is_valid,
+		"Validation failed: Expected on land"
+	);
+	assert!(rule.validate_footprint(&[land], &map).is_ok());
+	assert_eq!(rule.validate_footprint(&[land, water], &map), Err(water));
+	let outside = HexCoord::from_offset_pos(Chunk::SIZE, 0);
+	assert_eq!(rule.validate_footprint(&[outside], &map), Err(outside));
+
+	let rule = PlacementRule::Or(
+		Box::new(PlacementRule::OnWater),
+		Box::new(PlacementRule::Not(Box::new(PlacementRule::OnLand))),
+	);
+	assert!(rule.validate_footprint(&[water], &map).is_ok());
+	assert!(rule.validate_footprint(&[land], &map).is_err());
+	assert_eq!(rule.to_string(), "on water or not on land");
+
+	let rule = PlacementRule::All(vec![PlacementRule::OnLand, PlacementRule::NextToWater]);
+	assert!(
+		!rule.validate_placement(land, &map).is_valid,
+		"Validation failed: Inland"
+	);
+
+	let mut tile_manager = TileManager::default();
+	tile_manager.register_tile("grass", Default::default()).unwrap();
+	tile_manager.register_tile("sand", Default::default()).unwrap();
+	map.chunks[0].tile_types[land.to_chunk_local_index()] = 1;
+	let mut rule = PlacementRule::OnTileType(vec!["sand".to_string()].into());
+	assert!(
+		!rule.validate_placement(land, &map).is_valid,
+		"Validation failed: Tiles are not resolved"
+	);
+	rule.resolve_tiles(&tile_manager).unwrap();
+	assert!(
+		rule.validate_placement(land, &map).is_valid,
+		"Validation failed: Expected sand"
+	);
+	assert_eq!(rule.to_string(), "on sand");
+
+	let mut rule = PlacementRule::Not(Box::new(PlacementRule::OnTileType(
+		vec!["snow".to_string(), "grass".to_string()].into(),
+	)));
+	assert_eq!(
+		rule.resolve_tiles(&tile_manager),
+		Err(vec![TileRegistryError::MissingTile("snow".to_string())])
+	);
 }
 
 fn create_test_map() -> Map
@@ -103,6 +186,7 @@ fn create_test_map() -> Map
 		max_level: 5.0,
 		min_level: 0.0,
 		sealevel: 2.0,
+		tile_classes: Default::default(),
 	}
 }
 
@@ -2,11 +2,13 @@ mod always;
 mod next_to_water;
 mod not;
 mod on_land;
+mod on_tile_type;
 mod on_water;
 mod or;
 pub use always::*;
 pub use next_to_water::*;
 pub use not::*;
 pub use on_land::*;
+pub use on_tile_type::*;
 pub use on_water::*;
 pub use or::*;
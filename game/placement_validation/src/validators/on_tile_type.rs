@@ -0,0 +1,29 @@
+use hex::prelude::HexCoord;
+use world_generation::mapping::map::Map;
+
+use crate::{ValidationResult, traits::PlacementValidator, validators::Always};
+
+/// Valid when the tile is painted with one of `tile_types`
+#[derive(Default)]
+pub struct OnTileType<T: PlacementValidator = Always>
+{
+	pub tile_types: Vec<usize>,
+	pub inner: T,
+}
+
+impl<T: PlacementValidator> PlacementValidator for OnTileType<T>
+{
+	type Inner = T;
+	fn validate_self(&self, pos: HexCoord, map: &Map) -> ValidationResult
+	{
+		ValidationResult {
+			is_valid: self.tile_types.contains(&map.get_tile_type(&pos)),
+			display: Vec::default(),
+		}
+	}
+
+	fn get_inner(&self) -> Option<&Self::Inner>
+	{
+		Some(&self.inner)
+	}
+}
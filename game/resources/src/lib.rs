@@ -1,4 +1,5 @@
 pub mod resource_asset;
+pub mod resource_plugin;
 
 #[cfg(test)]
 mod tests;
@@ -1,11 +1,17 @@
-use asset_loader::create_asset_loader;
+use asset_loader::{PhosAsset, error::ValidateAsset};
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 use serde::{Deserialize, Serialize};
-use shared::Tier;
+use shared::{
+	Tier,
+	content_registry::{ContentError, ContentRegistry},
+	identifiers::{ResourceAmount, ResourceIdentifier},
+};
 
-#[derive(Asset, TypePath, Debug, Serialize, Deserialize)]
+#[derive(Asset, TypePath, Debug, Serialize, Deserialize, PhosAsset)]
+#[asset(extensions = ["res", "res.ron", "res.json", "res.toml"])]
 pub struct ResourceAsset {
+	/// Key other content refers to the resource by, e.g. `"iron_ore"`
 	pub identifier: String,
 	pub name: String,
 	pub description: String,
@@ -13,37 +19,73 @@ pub struct ResourceAsset {
 	pub tier: Tier,
 }
 
-create_asset_loader!(
-	ResourceAssetPlugin,
-	ResourceAssetLoader,
-	ResourceAsset,
-	&["res", "res.ron"],
-	;?
-);
+impl ValidateAsset for ResourceAsset {
+	fn validate(&self) -> Result<(), String> {
+		if self.identifier.is_empty() {
+			return Err(format!("Resource '{}' has no identifier", self.name));
+		}
+		return Ok(());
+	}
+}
 
 #[derive(Resource, AssetCollection)]
 pub struct ResourceDatabase {
 	#[asset(key = "resources", collection(typed))]
 	pub units: Vec<Handle<ResourceAsset>>,
+	/// Resources of the content packs in load order, see [`shared::content_pack::ContentPacks`]
+	#[asset(key = "pack_resources", collection(typed))]
+	pub pack_resources: Vec<Handle<ResourceAsset>>,
 }
 
 impl ResourceDatabase {
-	pub fn create_lookup(&self, assets: &Assets<ResourceAsset>) -> ResourceLookup {
-		let mut identifiers = Vec::with_capacity(self.units.len());
-		for handle in &self.units {
-			if let Some(asset) = assets.get(handle.id()) {
-				identifiers.push(asset.identifier.clone());
-			}
-		}
-		return ResourceLookup {
-			handles: self.units.clone(),
-			identifiers,
+	/// Builds the lookup from resource keys to [`ResourceIdentifier`]s, resources that are not loaded are skipped
+	///
+	/// Resources of content packs replace resources with the same key
+	pub fn create_lookup(&self, assets: &Assets<ResourceAsset>) -> Result<ResourceLookup, ContentError> {
+		let get_entry = |handle: &Handle<ResourceAsset>| {
+			return assets
+				.get(handle.id())
+				.map(|asset| (asset.identifier.clone(), handle.clone()));
 		};
+		return Ok(ResourceLookup {
+			registry: ContentRegistry::build_with_overrides(
+				"resource",
+				self.units.iter().filter_map(get_entry),
+				self.pack_resources.iter().filter_map(get_entry),
+			)?,
+		});
 	}
 }
 
+/// Resolves the resource keys of content files to [`ResourceIdentifier`]s
 #[derive(Resource)]
 pub struct ResourceLookup {
-	pub handles: Vec<Handle<ResourceAsset>>,
-	pub identifiers: Vec<String>,
+	registry: ContentRegistry<ResourceAsset>,
+}
+
+impl ResourceLookup {
+	pub fn get_id(&self, key: &str) -> Result<ResourceIdentifier, ContentError> {
+		return Ok(ResourceIdentifier(self.registry.get_id(key)? as u32));
+	}
+
+	/// Resolves the resource of an amount from a content file
+	pub fn resolve(&self, amount: &ResourceAmount) -> Result<(ResourceIdentifier, u32), ContentError> {
+		return Ok((self.get_id(&amount.resource)?, amount.qty));
+	}
+
+	pub fn get_key(&self, id: ResourceIdentifier) -> Option<&str> {
+		return self.registry.get_key(id.0 as usize);
+	}
+
+	pub fn get_handle(&self, id: ResourceIdentifier) -> Option<&Handle<ResourceAsset>> {
+		return self.registry.get_handle(id.0 as usize);
+	}
+
+	pub fn len(&self) -> usize {
+		return self.registry.len();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.registry.is_empty();
+	}
 }
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use bevy_asset_loader::loading_state::{
+	LoadingStateAppExt,
+	config::{ConfigureLoadingState, LoadingStateConfig},
+};
+use shared::states::AssetLoadState;
+
+use crate::resource_asset::{ResourceAsset, ResourceAssetPlugin, ResourceDatabase};
+
+pub struct ResourcePlugin;
+
+impl Plugin for ResourcePlugin
+{
+	fn build(&self, app: &mut App)
+	{
+		app.add_plugins(ResourceAssetPlugin);
+		app.configure_loading_state(
+			LoadingStateConfig::new(AssetLoadState::Loading).load_collection::<ResourceDatabase>(),
+		);
+		app.add_systems(OnEnter(AssetLoadState::FinalizeAssets), create_resource_lookup);
+	}
+}
+
+/// Resolves the resource keys once every resource is loaded, content that refers to resources is checked after it
+pub fn create_resource_lookup(
+	mut commands: Commands,
+	db: Res<ResourceDatabase>,
+	resource_assets: Res<Assets<ResourceAsset>>,
+)
+{
+	match db.create_lookup(&resource_assets) {
+		Ok(lookup) => {
+			info!("Registered {} resources", lookup.len());
+			commands.insert_resource(lookup);
+		}
+		Err(err) => error!("{}", err),
+	}
+}
@@ -1,7 +1,7 @@
 use bevy::reflect::Reflect;
 use serde::{Deserialize, Serialize};
 
-#[derive(Default, Reflect, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Default, Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BuildingIdentifier(pub usize);
 
 impl From<i32> for BuildingIdentifier
@@ -0,0 +1,476 @@
+use std::{
+	collections::HashMap,
+	fmt::Display,
+	fs::File,
+	path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::content_registry::ContentKind;
+
+/// Folder of the asset folder content packs are read from, every pack is a folder or a zip archive in it
+pub const PACK_FOLDER: &str = "packs";
+/// Folder of [`PACK_FOLDER`] zip archives are extracted to, the asset server can only read folders
+pub const EXTRACTED_FOLDER: &str = ".extracted";
+/// File at the root of every content pack
+pub const MANIFEST_FILE: &str = "pack.ron";
+
+/// The `pack.ron` of a content pack
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PackManifest
+{
+	pub id: String,
+	pub name: String,
+	pub version: String,
+	/// Packs with a lower load order load first, content of later packs overrides content with the same key
+	#[serde(default)]
+	pub load_order: i32,
+	/// Ids of the packs that have to load before this one
+	#[serde(default)]
+	pub dependencies: Vec<String>,
+	#[serde(default = "default_enabled")]
+	pub enabled: bool,
+}
+
+fn default_enabled() -> bool
+{
+	return true;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentPackError
+{
+	Io
+	{
+		path: PathBuf, message: String
+	},
+	Manifest
+	{
+		path: PathBuf, message: String
+	},
+	Archive
+	{
+		path: PathBuf, message: String
+	},
+	DuplicatePack
+	{
+		id: String
+	},
+	MissingDependency
+	{
+		pack: String, dependency: String
+	},
+	DependencyCycle
+	{
+		packs: Vec<String>
+	},
+}
+
+impl Display for ContentPackError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			ContentPackError::Io { path, message } => write!(f, "Failed to read {}: {}", path.display(), message),
+			ContentPackError::Manifest { path, message } => {
+				write!(f, "Invalid pack manifest {}: {}", path.display(), message)
+			}
+			ContentPackError::Archive { path, message } => {
+				write!(f, "Failed to extract pack archive {}: {}", path.display(), message)
+			}
+			ContentPackError::DuplicatePack { id } => write!(f, "More than one content pack uses the id '{}'", id),
+			ContentPackError::MissingDependency { pack, dependency } => write!(
+				f,
+				"Content pack '{}' requires '{}' which is missing or failed to load",
+				pack, dependency
+			),
+			ContentPackError::DependencyCycle { packs } => {
+				write!(f, "Content packs depend on each other: {}", packs.join(", "))
+			}
+		};
+	}
+}
+
+impl std::error::Error for ContentPackError {}
+
+/// A content pack that passed load order resolution
+#[derive(Debug, Clone)]
+pub struct ContentPack
+{
+	pub manifest: PackManifest,
+	/// Asset path of the pack folder, `packs/frontier`
+	pub asset_path: String,
+	/// Asset paths of the content files of the pack, sorted
+	pub files: HashMap<ContentKind, Vec<String>>,
+}
+
+/// The enabled content packs in load order
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ContentPacks
+{
+	packs: Vec<ContentPack>,
+}
+
+impl ContentPacks
+{
+	/// Reads the packs of the [`PACK_FOLDER`] of `asset_root` and orders them
+	///
+	/// Packs with an invalid manifest, missing dependencies or dependency cycles are reported and left out
+	pub fn discover(asset_root: &Path) -> (Self, Vec<ContentPackError>)
+	{
+		let pack_root = asset_root.join(PACK_FOLDER);
+		let mut errors = Vec::new();
+		if !pack_root.is_dir() {
+			return (Self::default(), errors);
+		}
+		let mut entries: Vec<PathBuf> = match std::fs::read_dir(&pack_root) {
+			Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+			Err(err) => {
+				errors.push(ContentPackError::Io {
+					path: pack_root,
+					message: err.to_string(),
+				});
+				return (Self::default(), errors);
+			}
+		};
+		entries.sort();
+
+		let mut found = Vec::new();
+		for entry in entries {
+			let dir = if entry.is_dir() {
+				if entry.file_name().is_some_and(|name| name == EXTRACTED_FOLDER) {
+					continue;
+				}
+				entry
+			} else if entry.extension().is_some_and(|ext| ext == "zip") {
+				match extract_archive(&entry, &pack_root.join(EXTRACTED_FOLDER)) {
+					Ok(dir) => dir,
+					Err(err) => {
+						errors.push(err);
+						continue;
+					}
+				}
+			} else {
+				continue;
+			};
+			match read_manifest(&dir.join(MANIFEST_FILE)) {
+				Ok(manifest) if manifest.enabled => found.push((manifest, dir)),
+				Ok(_) => {}
+				Err(err) => errors.push(err),
+			}
+		}
+
+		let manifests: Vec<_> = found.iter().map(|(manifest, _)| manifest.clone()).collect();
+		let (order, order_errors) = resolve_load_order(&manifests);
+		errors.extend(order_errors);
+
+		let mut packs = Vec::with_capacity(order.len());
+		for index in order {
+			let (manifest, dir) = &found[index];
+			let mut files = Vec::new();
+			if let Err(err) = collect_files(dir, &mut files) {
+				errors.push(ContentPackError::Io {
+					path: dir.clone(),
+					message: err.to_string(),
+				});
+				continue;
+			}
+			files.sort();
+			let mut pack = ContentPack {
+				manifest: manifest.clone(),
+				asset_path: get_asset_path(asset_root, dir),
+				files: HashMap::new(),
+			};
+			for file in files {
+				if let Some(kind) = ContentKind::from_path(&file) {
+					pack.files
+						.entry(kind)
+						.or_default()
+						.push(get_asset_path(asset_root, &file));
+				}
+			}
+			packs.push(pack);
+		}
+		return (Self { packs }, errors);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &ContentPack>
+	{
+		return self.packs.iter();
+	}
+
+	/// Asset paths of the content files of a kind of every pack, in load order
+	pub fn get_files(&self, kind: ContentKind) -> Vec<String>
+	{
+		return self
+			.packs
+			.iter()
+			.filter_map(|pack| pack.files.get(&kind))
+			.flatten()
+			.cloned()
+			.collect();
+	}
+
+	pub fn len(&self) -> usize
+	{
+		return self.packs.len();
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		return self.packs.is_empty();
+	}
+}
+
+/// Orders packs by their dependencies, then load order, then id
+///
+/// Returns the indices of the packs that can load, packs with a duplicate id, a missing dependency or in a cycle are left out
+pub fn resolve_load_order(manifests: &[PackManifest]) -> (Vec<usize>, Vec<ContentPackError>)
+{
+	let mut errors = Vec::new();
+	let mut active = vec![true; manifests.len()];
+	let mut ids = HashMap::new();
+	for (i, manifest) in manifests.iter().enumerate() {
+		if ids.contains_key(manifest.id.as_str()) {
+			errors.push(ContentPackError::DuplicatePack {
+				id: manifest.id.clone(),
+			});
+			active[i] = false;
+			continue;
+		}
+		ids.insert(manifest.id.as_str(), i);
+	}
+
+	//Leaving out a pack can break the packs that depend on it
+	let mut changed = true;
+	while changed {
+		changed = false;
+		for (i, manifest) in manifests.iter().enumerate() {
+			if !active[i] {
+				continue;
+			}
+			let missing = manifest
+				.dependencies
+				.iter()
+				.find(|dep| !ids.get(dep.as_str()).is_some_and(|&j| active[j]));
+			if let Some(dep) = missing {
+				errors.push(ContentPackError::MissingDependency {
+					pack: manifest.id.clone(),
+					dependency: dep.clone(),
+				});
+				active[i] = false;
+				changed = true;
+			}
+		}
+	}
+
+	let mut loaded = vec![false; manifests.len()];
+	let mut order = Vec::with_capacity(manifests.len());
+	loop {
+		let next = (0..manifests.len())
+			.filter(|&i| active[i] && !loaded[i])
+			.filter(|&i| manifests[i].dependencies.iter().all(|dep| loaded[ids[dep.as_str()]]))
+			.min_by_key(|&i| (manifests[i].load_order, &manifests[i].id));
+		let Some(next) = next else {
+			break;
+		};
+		loaded[next] = true;
+		order.push(next);
+	}
+
+	let cycle: Vec<_> = (0..manifests.len())
+		.filter(|&i| active[i] && !loaded[i])
+		.map(|i| manifests[i].id.clone())
+		.collect();
+	if !cycle.is_empty() {
+		errors.push(ContentPackError::DependencyCycle { packs: cycle });
+	}
+	return (order, errors);
+}
+
+fn read_manifest(path: &Path) -> Result<PackManifest, ContentPackError>
+{
+	let text = std::fs::read_to_string(path).map_err(|err| ContentPackError::Io {
+		path: path.to_path_buf(),
+		message: err.to_string(),
+	})?;
+	return ron::from_str(&text).map_err(|err| ContentPackError::Manifest {
+		path: path.to_path_buf(),
+		message: err.to_string(),
+	});
+}
+
+/// Extracts a zip archive to a folder named after it, archives that wrap the pack in a single folder are supported
+fn extract_archive(archive: &Path, target_root: &Path) -> Result<PathBuf, ContentPackError>
+{
+	let error = |message: String| ContentPackError::Archive {
+		path: archive.to_path_buf(),
+		message,
+	};
+	let name = archive
+		.file_stem()
+		.ok_or_else(|| error("The archive has no name".into()))?;
+	let target = target_root.join(name);
+	if target.exists() {
+		std::fs::remove_dir_all(&target).map_err(|err| error(err.to_string()))?;
+	}
+	let file = File::open(archive).map_err(|err| error(err.to_string()))?;
+	let mut zip = zip::ZipArchive::new(file).map_err(|err| error(err.to_string()))?;
+	zip.extract(&target).map_err(|err| error(err.to_string()))?;
+
+	if target.join(MANIFEST_FILE).is_file() {
+		return Ok(target);
+	}
+	let dirs: Vec<_> = std::fs::read_dir(&target)
+		.map_err(|err| error(err.to_string()))?
+		.filter_map(|e| e.ok())
+		.map(|e| e.path())
+		.filter(|path| path.is_dir())
+		.collect();
+	if let [dir] = dirs.as_slice() {
+		return Ok(dir.clone());
+	}
+	return Ok(target);
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()>
+{
+	for entry in std::fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			collect_files(&path, files)?;
+		} else {
+			files.push(path);
+		}
+	}
+	return Ok(());
+}
+
+/// Path relative to the asset folder with `/` separators, the form the asset server expects
+fn get_asset_path(asset_root: &Path, path: &Path) -> String
+{
+	let path = path.strip_prefix(asset_root).unwrap_or(path);
+	return path
+		.components()
+		.map(|c| c.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/");
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn manifest(id: &str, load_order: i32, dependencies: &[&str]) -> PackManifest
+	{
+		return PackManifest {
+			id: id.into(),
+			name: id.into(),
+			version: "1.0.0".into(),
+			load_order,
+			dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+			enabled: true,
+		};
+	}
+
+	fn ids(manifests: &[PackManifest], order: &[usize]) -> Vec<String>
+	{
+		return order.iter().map(|&i| manifests[i].id.clone()).collect();
+	}
+
+	#[test]
+	fn load_order()
+	{
+		let manifests = [
+			manifest("tanks", 0, &["weapons"]),
+			manifest("weapons", 5, &[]),
+			manifest("biomes", 1, &[]),
+			manifest("alpha", 1, &[]),
+		];
+		let (order, errors) = resolve_load_order(&manifests);
+
+		assert!(errors.is_empty());
+		assert_eq!(ids(&manifests, &order), ["alpha", "biomes", "weapons", "tanks"]);
+	}
+
+	#[test]
+	fn invalid_packs()
+	{
+		let manifests = [
+			manifest("base", 0, &[]),
+			manifest("base", 0, &[]),
+			manifest("lasers", 0, &["weapons"]),
+			manifest("turrets", 0, &["lasers"]),
+			manifest("a", 0, &["b"]),
+			manifest("b", 0, &["a"]),
+			manifest("mechs", 0, &["base"]),
+		];
+		let (order, errors) = resolve_load_order(&manifests);
+
+		assert_eq!(ids(&manifests, &order), ["base", "mechs"]);
+		assert_eq!(errors.len(), 4);
+		assert!(errors.contains(&ContentPackError::DuplicatePack { id: "base".into() }));
+		assert!(errors.contains(&ContentPackError::MissingDependency {
+			pack: "turrets".into(),
+			dependency: "lasers".into()
+		}));
+		assert!(errors.contains(&ContentPackError::DependencyCycle {
+			packs: vec!["a".into(), "b".into()]
+		}));
+	}
+
+	#[test]
+	fn discover()
+	{
+		let root = std::env::temp_dir().join("phos_content_pack_discover");
+		let _ = std::fs::remove_dir_all(&root);
+		let write = |path: &str, text: &str| {
+			let path = root.join(path);
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(path, text).unwrap();
+		};
+		write(
+			"packs/tanks/pack.ron",
+			r#"(id: "tanks", name: "Tanks", version: "1.0.0", dependencies: ["base"])"#,
+		);
+		write("packs/tanks/units/tank.unit.ron", "()");
+		write("packs/tanks/models/tank.glb", "");
+		write(
+			"packs/base/pack.ron",
+			r#"(id: "base", name: "Base", version: "1.0.0", load_order: 1)"#,
+		);
+		write("packs/base/buildings/mine.building.ron", "()");
+		write(
+			"packs/off/pack.ron",
+			r#"(id: "off", name: "Off", version: "1.0.0", enabled: false)"#,
+		);
+		write("packs/broken/pack.ron", "(");
+
+		let (packs, errors) = ContentPacks::discover(&root);
+		let _ = std::fs::remove_dir_all(&root);
+
+		assert_eq!(errors.len(), 1);
+		assert!(matches!(errors[0], ContentPackError::Manifest { .. }));
+		let ids: Vec<_> = packs.iter().map(|p| p.manifest.id.as_str()).collect();
+		assert_eq!(ids, ["base", "tanks"]);
+		assert_eq!(packs.iter().next().unwrap().asset_path, "packs/base");
+		assert_eq!(packs.get_files(ContentKind::Unit), ["packs/tanks/units/tank.unit.ron"]);
+		assert_eq!(
+			packs.get_files(ContentKind::Building),
+			["packs/base/buildings/mine.building.ron"]
+		);
+		assert!(packs.get_files(ContentKind::Biome).is_empty());
+	}
+
+	#[test]
+	fn manifest_defaults()
+	{
+		let manifest: PackManifest = ron::from_str(r#"(id: "tanks", name: "Tanks", version: "0.1.0")"#).unwrap();
+		assert_eq!(manifest.load_order, 0);
+		assert!(manifest.dependencies.is_empty());
+		assert!(manifest.enabled);
+	}
+}
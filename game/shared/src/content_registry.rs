@@ -0,0 +1,255 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentError
+{
+	DuplicateKey
+	{
+		kind: &'static str, key: String
+	},
+	UnknownKey
+	{
+		kind: &'static str, key: String
+	},
+}
+
+impl Display for ContentError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		return match self {
+			ContentError::DuplicateKey { kind, key } => write!(f, "More than one {} uses the key '{}'", kind, key),
+			ContentError::UnknownKey { kind, key } => write!(f, "There is no {} with the key '{}'", kind, key),
+		};
+	}
+}
+
+impl std::error::Error for ContentError {}
+
+/// The kinds of content files, named after the extension before the format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentKind
+{
+	Building,
+	Unit,
+	Resource,
+	Biome,
+	Mapper,
+	Tile,
+}
+
+impl ContentKind
+{
+	/// Gets the kind from a file name, `mine.building`, `mine.building.ron`, `.json` and `.toml` are all buildings
+	pub fn from_path(path: &Path) -> Option<Self>
+	{
+		let name = path.file_name()?.to_str()?;
+		let name = [".ron", ".json", ".toml"]
+			.iter()
+			.find_map(|format| name.strip_suffix(format))
+			.unwrap_or(name);
+		return match name.rsplit_once('.')?.1 {
+			"building" => Some(ContentKind::Building),
+			"unit" => Some(ContentKind::Unit),
+			"res" => Some(ContentKind::Resource),
+			"biome" => Some(ContentKind::Biome),
+			"mapper" => Some(ContentKind::Mapper),
+			"tile" => Some(ContentKind::Tile),
+			_ => None,
+		};
+	}
+}
+
+/// Maps the string keys content files use to refer to each other to dense ids.
+///
+/// Ids follow the sorted keys so they do not depend on the order files are loaded in.
+#[derive(Debug)]
+pub struct ContentRegistry<A: Asset>
+{
+	kind: &'static str,
+	keys: Vec<String>,
+	handles: Vec<Handle<A>>,
+	ids: HashMap<String, usize>,
+}
+
+impl<A: Asset> ContentRegistry<A>
+{
+	/// `kind` names the content in errors, e.g. `"resource"`
+	pub fn build(
+		kind: &'static str,
+		entries: impl IntoIterator<Item = (String, Handle<A>)>,
+	) -> Result<Self, ContentError>
+	{
+		let mut entries: Vec<_> = entries.into_iter().collect();
+		entries.sort_by(|a, b| a.0.cmp(&b.0));
+		let mut registry = Self {
+			kind,
+			keys: Vec::with_capacity(entries.len()),
+			handles: Vec::with_capacity(entries.len()),
+			ids: HashMap::with_capacity(entries.len()),
+		};
+		for (key, handle) in entries {
+			if registry.ids.insert(key.clone(), registry.keys.len()).is_some() {
+				return Err(ContentError::DuplicateKey { kind, key });
+			}
+			registry.keys.push(key);
+			registry.handles.push(handle);
+		}
+		return Ok(registry);
+	}
+
+	/// Builds the registry with the content of the content packs layered on top
+	///
+	/// Keys in `entries` have to be unique, `overrides` are in pack load order and replace entries with the same key
+	pub fn build_with_overrides(
+		kind: &'static str,
+		entries: impl IntoIterator<Item = (String, Handle<A>)>,
+		overrides: impl IntoIterator<Item = (String, Handle<A>)>,
+	) -> Result<Self, ContentError>
+	{
+		let mut merged = HashMap::new();
+		for (key, handle) in entries {
+			if merged.contains_key(&key) {
+				return Err(ContentError::DuplicateKey { kind, key });
+			}
+			merged.insert(key, handle);
+		}
+		for (key, handle) in overrides {
+			if merged.contains_key(&key) {
+				info!("A content pack overrides the {} '{}'", kind, key);
+			}
+			merged.insert(key, handle);
+		}
+		return Self::build(kind, merged);
+	}
+
+	pub fn get_id(&self, key: &str) -> Result<usize, ContentError>
+	{
+		return self.ids.get(key).copied().ok_or_else(|| ContentError::UnknownKey {
+			kind: self.kind,
+			key: key.to_string(),
+		});
+	}
+
+	/// Resolves every key, the errors list all unknown keys instead of only the first
+	pub fn get_ids<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Result<Vec<usize>, Vec<ContentError>>
+	{
+		let (ids, errors): (Vec<_>, Vec<_>) = keys.into_iter().map(|key| self.get_id(key)).partition(|r| r.is_ok());
+		if !errors.is_empty() {
+			return Err(errors.into_iter().filter_map(|r| r.err()).collect());
+		}
+		return Ok(ids.into_iter().filter_map(|r| r.ok()).collect());
+	}
+
+	pub fn get_key(&self, id: usize) -> Option<&str>
+	{
+		return self.keys.get(id).map(|k| k.as_str());
+	}
+
+	pub fn get_handle(&self, id: usize) -> Option<&Handle<A>>
+	{
+		return self.handles.get(id);
+	}
+
+	pub fn len(&self) -> usize
+	{
+		return self.keys.len();
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		return self.keys.is_empty();
+	}
+}
+
+/// The key of content that does not set one, the file name without extensions, `mine.building.ron` is `mine`
+pub fn get_key_from_path(path: &Path) -> Option<String>
+{
+	let name = path.file_name()?.to_str()?;
+	return name.split('.').next().map(|n| n.to_string());
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[derive(Asset, TypePath)]
+	struct TestAsset;
+
+	#[test]
+	fn stable_ids()
+	{
+		let entries = ["iron_ore", "copper_ore", "coal"].map(|k| (k.to_string(), Handle::<TestAsset>::default()));
+		let registry = ContentRegistry::build("resource", entries.clone()).unwrap();
+		let mut reversed = entries.to_vec();
+		reversed.reverse();
+		let other = ContentRegistry::build("resource", reversed).unwrap();
+
+		assert_eq!(registry.len(), 3);
+		assert_eq!(registry.get_id("coal"), Ok(0));
+		assert_eq!(registry.get_id("iron_ore"), Ok(2));
+		assert_eq!(other.get_id("iron_ore"), Ok(2));
+		assert_eq!(registry.get_key(1), Some("copper_ore"));
+		assert_eq!(
+			registry.get_id("gold"),
+			Err(ContentError::UnknownKey {
+				kind: "resource",
+				key: "gold".into()
+			})
+		);
+		assert_eq!(registry.get_ids(["coal", "iron_ore"]), Ok(vec![0, 2]));
+		assert_eq!(registry.get_ids(["coal", "gold", "tin"]).unwrap_err().len(), 2);
+
+		let mut duplicate = entries.to_vec();
+		duplicate.push(("coal".into(), Handle::default()));
+		assert!(matches!(
+			ContentRegistry::build("resource", duplicate),
+			Err(ContentError::DuplicateKey { .. })
+		));
+	}
+
+	#[test]
+	fn overrides()
+	{
+		let entries = ["coal", "iron_ore"].map(|k| (k.to_string(), Handle::<TestAsset>::default()));
+		let overrides = ["iron_ore", "uranium", "uranium"].map(|k| (k.to_string(), Handle::<TestAsset>::default()));
+		let registry = ContentRegistry::build_with_overrides("resource", entries.clone(), overrides).unwrap();
+
+		assert_eq!(registry.len(), 3);
+		assert_eq!(registry.get_id("uranium"), Ok(2));
+
+		let mut duplicate = entries.to_vec();
+		duplicate.push(("coal".into(), Handle::default()));
+		assert!(matches!(
+			ContentRegistry::build_with_overrides("resource", duplicate, []),
+			Err(ContentError::DuplicateKey { .. })
+		));
+	}
+
+	#[test]
+	fn kind_from_path()
+	{
+		let kind = |path: &str| ContentKind::from_path(Path::new(path));
+		assert_eq!(kind("buildings/mine.building"), Some(ContentKind::Building));
+		assert_eq!(kind("buildings/mine.building.ron"), Some(ContentKind::Building));
+		assert_eq!(kind("units/tank.unit.json"), Some(ContentKind::Unit));
+		assert_eq!(kind("resources/iron.res.toml"), Some(ContentKind::Resource));
+		assert_eq!(kind("tiles/grass.tile.ron"), Some(ContentKind::Tile));
+		assert_eq!(kind("models/mine.glb"), None);
+		assert_eq!(kind("phos.assets.ron"), None);
+		assert_eq!(kind("tile"), None);
+	}
+
+	#[test]
+	fn key_from_path()
+	{
+		assert_eq!(
+			get_key_from_path(Path::new("buildings/mine.building.ron")),
+			Some("mine".into())
+		);
+		assert_eq!(get_key_from_path(Path::new("hq.building")), Some("hq".into()));
+	}
+}
@@ -49,6 +49,7 @@ impl CoordsCollection
 		return self;
 	}
 
+	/// Rotates the points around the origin, then applies the translation
 	pub fn get_coords(&self) -> Vec<HexCoord>
 	{
 		let center = HexCoord::from_axial(self.origin);
@@ -56,6 +57,7 @@ impl CoordsCollection
 			.points
 			.iter()
 			.map(|p| HexCoord::from_axial(p + self.origin).rotate_around(&center, self.rotation))
+			.map(|c| HexCoord::from_axial(c.hex.xy() + self.translation))
 			.collect();
 	}
 }
@@ -67,3 +69,24 @@ impl From<CoordsCollection> for Vec<HexCoord>
 		value.get_coords()
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn rotation_and_translation()
+	{
+		let position = HexCoord::from_axial(IVec2::new(10, 4));
+		let coords = CoordsCollection::from_points(vec![IVec2::ZERO, IVec2::X])
+			.with_rotation(1)
+			.with_translation(&position)
+			.get_coords();
+		let rotated = HexCoord::from_axial(IVec2::X).rotate_around(&HexCoord::from_axial(IVec2::ZERO), 1);
+
+		assert_eq!(coords[0], position);
+		assert_eq!(coords[1].hex.xy(), rotated.hex.xy() + position.hex.xy());
+		assert_ne!(coords[1].hex.xy(), IVec2::new(11, 4));
+	}
+}
@@ -1,15 +1,47 @@
 use bevy::prelude::*;
 use hex::prelude::*;
 
+use crate::building::BuildingIdentifier;
+
 #[derive(Message)]
 pub enum TileModifiedEvent
 {
 	HeightChanged(HexCoord, f32),
+	/// The tile was given the tile type with this id, see `Map::set_tile_type`
 	TypeChanged(HexCoord, usize),
 }
 
+/// Changes the tile type of a tile, [`TileModifiedEvent::TypeChanged`] is sent once the map is updated
+#[derive(Message)]
+pub struct ChangeTileType
+{
+	pub coord: HexCoord,
+	/// Runtime id of the new tile type
+	pub tile_type: usize,
+}
+
 #[derive(Message)]
 pub struct ChunkModifiedEvent
 {
 	pub index: usize,
 }
+
+/// Sent when the sea level of the map changes
+#[derive(Message)]
+pub struct SeaLevelChangedEvent
+{
+	pub sealevel: f32,
+	/// Tiles that went from land to water or from water to land
+	pub tiles: Vec<HexCoord>,
+}
+
+/// Sent after a building was removed from the map and despawned
+#[derive(Message)]
+pub struct BuildingRemoved
+{
+	pub entity: Entity,
+	pub building: BuildingIdentifier,
+	pub coord: HexCoord,
+	/// Every tile the building covered, these are free now
+	pub footprint: Vec<HexCoord>,
+}
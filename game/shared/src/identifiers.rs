@@ -1,15 +1,20 @@
 use bevy::reflect::Reflect;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Reflect)]
-pub struct ResourceIdentifier
+/// Dense runtime id of a resource, assigned by the `ResourceLookup` and not stable between content changes
+#[derive(Serialize, Deserialize, Debug, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceIdentifier(pub u32);
+
+/// An amount of a resource in a content file, the resource is referred to by its key
+#[derive(Serialize, Deserialize, Debug, Reflect, Clone, PartialEq, Eq)]
+pub struct ResourceAmount
 {
-	pub id: u32,
+	pub resource: String,
 	pub qty: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Reflect)]
-pub struct UnitIdentifier(u32);
+#[derive(Serialize, Deserialize, Debug, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnitIdentifier(pub u32);
 
 #[derive(Serialize, Deserialize, Debug, Reflect)]
 pub struct TileIdentifier(u32);
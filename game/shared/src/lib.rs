@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 pub mod animation_plugin;
 pub mod building;
 pub mod component_defination;
+pub mod content_pack;
+pub mod content_registry;
 pub mod coords;
 pub mod despawn;
 pub mod events;
@@ -12,6 +14,7 @@ pub mod prefab_defination;
 pub mod resources;
 pub mod sets;
 pub mod states;
+pub mod stockpile;
 pub mod tags;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::identifiers::ResourceAmount;
+
+/// Resources the colony has stored, by resource key
+#[derive(Resource, Default, Debug, Clone)]
+pub struct ResourceStockpile
+{
+	amounts: HashMap<String, u32>,
+}
+
+impl ResourceStockpile
+{
+	pub fn get(&self, resource: &str) -> u32
+	{
+		return self.amounts.get(resource).copied().unwrap_or(0);
+	}
+
+	pub fn add(&mut self, amount: &ResourceAmount)
+	{
+		let stored = self.amounts.entry(amount.resource.clone()).or_default();
+		*stored = stored.saturating_add(amount.qty);
+	}
+
+	pub fn add_all(&mut self, amounts: &[ResourceAmount])
+	{
+		for amount in amounts {
+			self.add(amount);
+		}
+	}
+
+	pub fn has_all(&self, amounts: &[ResourceAmount]) -> bool
+	{
+		return amounts.iter().all(|amount| self.get(&amount.resource) >= amount.qty);
+	}
+
+	/// Takes every amount, nothing is taken when any of them is not stored
+	pub fn remove_all(&mut self, amounts: &[ResourceAmount]) -> bool
+	{
+		if !self.has_all(amounts) {
+			return false;
+		}
+		for amount in amounts {
+			if let Some(stored) = self.amounts.get_mut(&amount.resource) {
+				*stored -= amount.qty;
+			}
+		}
+		return true;
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn add_and_remove()
+	{
+		let amount = |resource: &str, qty| ResourceAmount {
+			resource: resource.into(),
+			qty,
+		};
+		let mut stockpile = ResourceStockpile::default();
+		stockpile.add_all(&[amount("iron_ore", 10), amount("coal", 2), amount("iron_ore", 5)]);
+		assert_eq!(stockpile.get("iron_ore"), 15);
+
+		assert!(!stockpile.remove_all(&[amount("iron_ore", 5), amount("coal", 3)]));
+		assert_eq!(stockpile.get("iron_ore"), 15);
+		assert!(stockpile.remove_all(&[amount("iron_ore", 5), amount("coal", 2)]));
+		assert_eq!(stockpile.get("iron_ore"), 10);
+		assert_eq!(stockpile.get("coal"), 0);
+		assert_eq!(stockpile.get("gold"), 0);
+	}
+}
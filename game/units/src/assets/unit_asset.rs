@@ -1,17 +1,22 @@
-use asset_loader::create_asset_loader;
+use asset_loader::{PhosAsset, error::ValidateAsset};
 use bevy::{ecs::world::CommandQueue, prelude::*};
 use serde::{Deserialize, Serialize};
 
 use crate::components::{AirUnit, LandUnit, NavalUnit, Unit, UnitDomain};
 
-#[derive(Asset, TypePath, Debug, Serialize, Deserialize)]
+#[derive(Asset, TypePath, Debug, Serialize, Deserialize, PhosAsset)]
+#[asset(extensions = ["unit", "unit.ron", "unit.json", "unit.toml"])]
 pub struct UnitAsset
 {
+	/// Key other content refers to the unit by, the file name is used when it is empty
+	#[serde(default)]
+	pub identifier: String,
 	pub name: String,
 	pub description: String,
 	pub size: u32,
 	pub prefab_path: String,
 	#[serde(skip)]
+	#[asset(path = "prefab_path")]
 	pub prefab: Handle<Gltf>,
 	pub unit_type: UnitType,
 	pub domain: UnitDomain,
@@ -38,14 +43,7 @@ impl UnitAsset
 	}
 }
 
-create_asset_loader!(
-	UnitAssetPlugin,
-	UnitAssetLoader,
-	UnitAsset,
-	&["unit", "unit.ron"],
-	prefab_path -> prefab
-	;?
-);
+impl ValidateAsset for UnitAsset {}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum UnitType
@@ -1,5 +1,9 @@
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
+use shared::{
+	content_registry::{ContentError, ContentRegistry, get_key_from_path},
+	identifiers::UnitIdentifier,
+};
 
 use super::unit_asset::UnitAsset;
 
@@ -7,4 +11,62 @@ use super::unit_asset::UnitAsset;
 pub struct UnitDatabase {
 	#[asset(key = "units", collection(typed))]
 	pub units: Vec<Handle<UnitAsset>>,
+	/// Units of the content packs in load order, see [`shared::content_pack::ContentPacks`]
+	#[asset(key = "pack_units", collection(typed))]
+	pub pack_units: Vec<Handle<UnitAsset>>,
+}
+
+impl UnitDatabase {
+	/// Builds the lookup from unit keys to [`UnitIdentifier`]s, units that are not loaded are skipped
+	///
+	/// Units of content packs replace units with the same key
+	pub fn create_lookup(&self, assets: &Assets<UnitAsset>, asset_server: &AssetServer) -> Result<UnitLookup, ContentError> {
+		let get_entry = |handle: &Handle<UnitAsset>| {
+			let asset = assets.get(handle.id())?;
+			let key = if asset.identifier.is_empty() {
+				asset_server
+					.get_path(handle.id())
+					.and_then(|path| get_key_from_path(path.path()))
+					.unwrap_or_else(|| asset.name.clone())
+			} else {
+				asset.identifier.clone()
+			};
+			return Some((key, handle.clone()));
+		};
+		return Ok(UnitLookup {
+			registry: ContentRegistry::build_with_overrides(
+				"unit",
+				self.units.iter().filter_map(get_entry),
+				self.pack_units.iter().filter_map(get_entry),
+			)?,
+		});
+	}
+}
+
+/// Resolves the unit keys of content files to [`UnitIdentifier`]s
+#[derive(Resource)]
+pub struct UnitLookup {
+	registry: ContentRegistry<UnitAsset>,
+}
+
+impl UnitLookup {
+	pub fn get_id(&self, key: &str) -> Result<UnitIdentifier, ContentError> {
+		return Ok(UnitIdentifier(self.registry.get_id(key)? as u32));
+	}
+
+	pub fn get_key(&self, id: UnitIdentifier) -> Option<&str> {
+		return self.registry.get_key(id.0 as usize);
+	}
+
+	pub fn get_handle(&self, id: UnitIdentifier) -> Option<&Handle<UnitAsset>> {
+		return self.registry.get_handle(id.0 as usize);
+	}
+
+	pub fn len(&self) -> usize {
+		return self.registry.len();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.registry.is_empty();
+	}
 }
@@ -9,6 +9,7 @@ pub struct NavData
 	pub tiles: Vec<NavTile>,
 	pub map_height: usize,
 	pub map_width: usize,
+	pub sealevel: f32,
 }
 
 impl NavData
@@ -20,12 +21,13 @@ impl NavData
 		for i in 0..6
 		{
 			let n = coord.get_neighbor(i);
-			if !self.is_in_bounds(&n)
+			if !self.is_in_bounds(&n) || self.get(&n).is_water
 			{
 				continue;
 			}
-			let n_height = self.get_height(&n);
-			neighbors.push((n, OrderedFloat((cur_height - n_height).abs().powi(2))));
+			let n_tile = self.get(&n);
+			let cost = (cur_height - n_tile.height).abs().powi(2) + n_tile.move_cost;
+			neighbors.push((n, OrderedFloat(cost)));
 		}
 		return neighbors;
 	}
@@ -44,7 +46,8 @@ impl NavData
 		return pos.is_in_bounds(self.map_height, self.map_width);
 	}
 
-	pub fn build(map: &Map) -> NavData
+	/// Builds the nav data, `move_costs` is the move cost of every tile type indexed by tile id
+	pub fn build(map: &Map, move_costs: &[f32]) -> NavData
 	{
 		#[cfg(feature = "tracing")]
 		let _path_span = info_span!("Build Nav Data").entered();
@@ -60,7 +63,8 @@ impl NavData
 				let tile = NavTile {
 					coord,
 					height,
-					move_cost: 1.0,
+					move_cost: get_move_cost(move_costs, map.get_tile_type(&coord)),
+					is_water: map.is_underwater(&coord),
 				};
 				tiles.push(tile);
 			}
@@ -70,15 +74,17 @@ impl NavData
 			tiles,
 			map_width: w,
 			map_height: h,
+			sealevel: map.sealevel,
 		};
 	}
 
-	pub fn update(&mut self, map: &Map)
+	pub fn update(&mut self, map: &Map, move_costs: &[f32])
 	{
 		#[cfg(feature = "tracing")]
 		let _path_span = info_span!("Update Nav Data").entered();
 		let h = map.get_tile_height();
 		let w = map.get_tile_width();
+		self.sealevel = map.sealevel;
 		for y in 0..h
 		{
 			for x in 0..w
@@ -88,7 +94,8 @@ impl NavData
 				let tile = NavTile {
 					coord,
 					height,
-					move_cost: 1.0,
+					move_cost: get_move_cost(move_costs, map.get_tile_type(&coord)),
+					is_water: map.is_underwater(&coord),
 				};
 				self.tiles[y * w + x] = tile;
 			}
@@ -99,7 +106,30 @@ impl NavData
 		let tile = &mut self.tiles[coord.to_index(self.map_width)];
 		tile.move_cost = move_cost;
 		tile.height = height;
+		//Same rule as `TileClass`, water only depends on the height
+		tile.is_water = height <= self.sealevel;
 	}
+
+	pub fn set_move_cost(&mut self, coord: &HexCoord, move_cost: f32)
+	{
+		self.tiles[coord.to_index(self.map_width)].move_cost = move_cost;
+	}
+
+	/// Updates which tiles are underwater after the sea level changed, only `tiles` are touched
+	pub fn update_sealevel(&mut self, sealevel: f32, tiles: &[HexCoord])
+	{
+		self.sealevel = sealevel;
+		for coord in tiles
+		{
+			let tile = &mut self.tiles[coord.to_index(self.map_width)];
+			tile.is_water = tile.height <= sealevel;
+		}
+	}
+}
+
+fn get_move_cost(move_costs: &[f32], tile_type: usize) -> f32
+{
+	return move_costs.get(tile_type).copied().unwrap_or(1.0);
 }
 
 #[derive(Clone)]
@@ -108,6 +138,7 @@ pub struct NavTile
 	pub height: f32,
 	pub move_cost: f32,
 	pub coord: HexCoord,
+	pub is_water: bool,
 }
 
 impl NavTile
@@ -5,15 +5,31 @@ use bevy::{
 	prelude::*,
 	tasks::{AsyncComputeTaskPool, futures},
 };
+use bevy_asset_loader::loading_state::{
+	LoadingStateAppExt,
+	config::{ConfigureLoadingState, LoadingStateConfig},
+};
 use hex::prelude::*;
 use pathfinding::prelude::astar;
-use shared::{events::TileModifiedEvent, resources::TileUnderCursor, sets::GameplaySystems};
-use world_generation::{prelude::Map, states::GeneratorState};
+use shared::{
+	events::{SeaLevelChangedEvent, TileModifiedEvent},
+	resources::TileUnderCursor,
+	sets::GameplaySystems,
+	states::AssetLoadState,
+};
+use world_generation::{
+	prelude::Map,
+	states::GeneratorState,
+	tile_manager::{TileAsset, TileManager},
+};
 
 #[cfg(debug_assertions)]
 use crate::units_debug_plugin::UnitsDebugPlugin;
 use crate::{
-	assets::unit_asset::UnitAssetPlugin,
+	assets::{
+		unit_asset::{UnitAsset, UnitAssetPlugin},
+		unit_database::UnitDatabase,
+	},
 	components::{Path, PathTask, PathTaskPending, Target, Unit},
 	nav_data::NavData,
 	resources::PathBatchId,
@@ -31,7 +47,8 @@ impl Plugin for UnitsPlugin
 		#[cfg(debug_assertions)]
 		app.add_plugins(UnitsDebugPlugin);
 
-		// app.configure_loading_state(LoadingStateConfig::new(AssetLoadState::Loading).load_collection::<UnitDatabase>());
+		app.configure_loading_state(LoadingStateConfig::new(AssetLoadState::Loading).load_collection::<UnitDatabase>());
+		app.add_systems(OnEnter(AssetLoadState::FinalizeAssets), create_unit_lookup);
 		app.add_systems(PostUpdate, build_navdata.run_if(in_state(GeneratorState::SpawnMap)));
 
 		app.add_systems(Update, units_control.in_set(GameplaySystems));
@@ -43,17 +60,58 @@ impl Plugin for UnitsPlugin
 	}
 }
 
-fn build_navdata(mut commands: Commands, map: Res<Map>)
+/// Resolves the unit keys once every unit is loaded
+fn create_unit_lookup(
+	mut commands: Commands,
+	db: Res<UnitDatabase>,
+	unit_assets: Res<Assets<UnitAsset>>,
+	asset_server: Res<AssetServer>,
+)
 {
-	let nav_data = NavData::build(&map);
+	match db.create_lookup(&unit_assets, &asset_server) {
+		Ok(lookup) => {
+			info!("Registered {} units", lookup.len());
+			commands.insert_resource(lookup);
+		}
+		Err(err) => error!("{}", err),
+	}
+}
+
+fn build_navdata(
+	mut commands: Commands,
+	map: Res<Map>,
+	tile_manager: Res<TileManager>,
+	tile_assets: Res<Assets<TileAsset>>,
+)
+{
+	let nav_data = NavData::build(&map, &tile_manager.get_move_costs(&tile_assets));
 	commands.insert_resource(nav_data);
 }
 
-fn update_navdata(mut tile_updates: MessageReader<TileModifiedEvent>, mut nav_data: ResMut<NavData>)
+/// Keeps the nav data in sync with height, tile type and sea level changes
+pub fn update_navdata(
+	mut tile_updates: MessageReader<TileModifiedEvent>,
+	mut sealevel_updates: MessageReader<SeaLevelChangedEvent>,
+	mut nav_data: ResMut<NavData>,
+	tile_manager: Res<TileManager>,
+	tile_assets: Res<Assets<TileAsset>>,
+)
 {
+	for event in sealevel_updates.read() {
+		nav_data.update_sealevel(event.sealevel, &event.tiles);
+	}
 	for event in tile_updates.read() {
-		if let TileModifiedEvent::HeightChanged(coord, new_height) = event {
-			nav_data.update_tile(coord, *new_height, 1.0);
+		match event {
+			TileModifiedEvent::HeightChanged(coord, new_height) => {
+				let move_cost = nav_data.get(coord).move_cost;
+				nav_data.update_tile(coord, *new_height, move_cost);
+			}
+			TileModifiedEvent::TypeChanged(coord, tile_type) => {
+				let move_cost = tile_manager
+					.get_tile(*tile_type, &tile_assets)
+					.map_or(1.0, |t| t.move_cost);
+				nav_data.set_move_cost(coord, move_cost);
+			}
 		}
 	}
 }